@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+use core::alloc::Allocator;
 use core::mem::size_of;
 use core::ops::{BitAnd, BitOr, BitOrAssign, Shl};
 use num::{one, zero, One, Zero};
@@ -89,3 +91,61 @@ where
         set
     }
 }
+
+/// Set of `usize` of any size, stored as a bit for each integer up to the
+/// largest one, in words of 64 bits
+#[derive(Debug, Clone)]
+pub(crate) struct DynBitSet<A: Allocator> {
+    words: Vec<u64, A>,
+}
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+impl<A: Allocator> DynBitSet<A> {
+    /// An empty set with room for the integers below `capacity`
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        DynBitSet {
+            words: Vec::with_capacity_in((capacity + WORD_BITS - 1) / WORD_BITS, alloc),
+        }
+    }
+
+    /// Remove all the elements, keeping the memory for new ones
+    pub fn clear(&mut self) {
+        self.words.clear();
+    }
+
+    pub fn contains(&self, item: usize) -> bool {
+        self.words
+            .get(item / WORD_BITS)
+            .map_or(false, |word| word & (1 << (item % WORD_BITS)) != 0)
+    }
+
+    pub fn insert(&mut self, item: usize) {
+        let i = item / WORD_BITS;
+
+        if i >= self.words.len() {
+            self.words.resize(i + 1, 0);
+        }
+
+        self.words[i] |= 1 << (item % WORD_BITS);
+    }
+}
+
+#[test]
+fn dyn_membership() {
+    let bump = bumpalo::Bump::new();
+    let mut set = DynBitSet::with_capacity_in(64, &bump);
+
+    for item in [0, 5, 63, 64, 200] {
+        set.insert(item);
+    }
+
+    assert!(set.contains(0) && set.contains(63) && set.contains(64) && set.contains(200));
+    assert!(!set.contains(1) && !set.contains(201) && !set.contains(10_000));
+
+    set.clear();
+    assert!(!set.contains(0) && !set.contains(200));
+
+    set.insert(3);
+    assert!(set.contains(3) && !set.contains(5));
+}
@@ -1,21 +1,56 @@
+use crate::parse::{Expected, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
+use nom::error::ErrorKind;
+
+const DAY: u8 = 1;
+
+pub fn day01(input: &str) -> Result<Answers<'static>, ParseError> {
+    let model = parse(input)?;
+
+    Ok((part1(&model), part2(&model)))
+}
+
+/// Calories carried by the three elves carrying the most, most first
+pub type Model = heapless::Vec<u32, 4>;
+
 #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day01(input: &str) -> (u32, u32) {
-    let most_calories = input
+pub fn parse(input: &str) -> Result<Model, ParseError> {
+    let parse = |l: &str| {
+        str::parse::<u32>(l).map_err(|_| {
+            ParseError::at(
+                DAY,
+                input.as_bytes(),
+                l.as_bytes(),
+                Expected::Kind(ErrorKind::Digit),
+            )
+        })
+    };
+
+    input
         .split("\n\n")
-        .map(|elf| elf.lines().map(|l| str::parse::<u32>(l).unwrap()).sum())
-        .fold(heapless::Vec::<u32, 4>::new(), |mut top, calories| {
+        .map(|elf| elf.lines().map(parse).sum::<Result<u32, _>>())
+        .try_fold(Model::new(), |mut top, calories| {
+            let calories = calories?;
             let ix = top.partition_point(|x| x > &calories);
             top.insert(ix, calories).unwrap();
             top.truncate(3);
-            top
-        });
+            Ok(top)
+        })
+}
 
-    (most_calories[0], most_calories.into_iter().sum())
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1(most_calories: &Model) -> Answer<'static> {
+    most_calories[0].into()
 }
 
-#[test]
-fn both_parts() {
-    let example = r#"1000
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2(most_calories: &Model) -> Answer<'static> {
+    most_calories.iter().sum::<u32>().into()
+}
+
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"1000
 2000
 3000
 
@@ -29,7 +64,45 @@ fn both_parts() {
 9000
 
 10000
-"#;
-    assert_eq!(day01(example).0, 24000);
-    assert_eq!(day01(example).1, 45000);
+"#,
+    params: Params::NONE,
+    part1: Some("24000"),
+    part2: Some("45000"),
+}];
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "Calorie Counting"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        false
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day01", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        _bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        _params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        run_phases(parts, phase, || parse(input), part1, part2)
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
+}
+
+#[test]
+fn both_parts() {
+    crate::check_examples(&Day01);
 }
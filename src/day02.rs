@@ -1,40 +1,85 @@
 use nom::branch::*;
 use nom::character::complete::*;
 use nom::combinator::*;
-use nom::multi::*;
 use nom::sequence::*;
 
+use crate::nom_extra::fold_lines;
+use crate::parse::{parse_all, IResult, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
+
+const DAY: u8 = 2;
+
+pub fn day02(input: &str) -> Result<Answers<'static>, ParseError> {
+    let model = parse(input)?;
+
+    Ok((part1(&model), part2(&model)))
+}
+
+/// Number of rounds by play of the opponent and by second column (X, Y or Z)
+pub type Model = [[u32; 3]; 3];
+
 #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day02(input: &str) -> (u32, u32) {
-    fold_many0(
-        terminated(parse, line_ending),
-        || (0, 0),
-        |(part1, part2), (round1, round2)| {
-            let score1 = score(round1.me, round1.outcome());
-            let score2 = score(round2.me(), round2.outcome);
-            (part1 + score1, part2 + score2)
+pub fn parse(input: &str) -> Result<Model, ParseError> {
+    let rounds = fold_lines(
+        parse_round,
+        || [[0; 3]; 3],
+        |mut rounds, (opponent, column)| {
+            rounds[opponent as usize][column] += 1;
+            rounds
         },
-    )(input.as_bytes())
-    .unwrap()
-    .1
+    );
+
+    parse_all(DAY, input.as_bytes(), rounds)
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1(rounds: &Model) -> Answer<'static> {
+    total_score(rounds, |opponent, column| {
+        let round = Round1 {
+            opponent,
+            me: PLAYS[column],
+        };
+        score(round.me, round.outcome())
+    })
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2(rounds: &Model) -> Answer<'static> {
+    total_score(rounds, |opponent, column| {
+        let round = Round2 {
+            opponent,
+            outcome: OUTCOMES[column],
+        };
+        score(round.me(), round.outcome)
+    })
+}
+
+fn total_score(rounds: &Model, score: impl Fn(Play, usize) -> u32) -> Answer<'static> {
+    let mut total = 0;
+
+    for (opponent, counts) in PLAYS.into_iter().zip(rounds) {
+        for (column, count) in counts.iter().enumerate() {
+            total += count * score(opponent, column);
+        }
+    }
+
+    total.into()
 }
 
-fn parse(input: &[u8]) -> nom::IResult<&[u8], (Round1, Round2), ()> {
-    map(
-        separated_pair(
-            alt((
-                map(char('A'), |_| Play::Rock),
-                map(char('B'), |_| Play::Paper),
-                map(char('C'), |_| Play::Scissors),
-            )),
-            char(' '),
-            alt((
-                map(char('X'), |_| (Play::Rock, Outcome::Lose)),
-                map(char('Y'), |_| (Play::Paper, Outcome::Draw)),
-                map(char('Z'), |_| (Play::Scissors, Outcome::Win)),
-            )),
-        ),
-        |(opponent, (me, outcome))| (Round1 { opponent, me }, Round2 { opponent, outcome }),
+fn parse_round(input: &[u8]) -> IResult<&[u8], (Play, usize)> {
+    separated_pair(
+        alt((
+            map(char('A'), |_| Play::Rock),
+            map(char('B'), |_| Play::Paper),
+            map(char('C'), |_| Play::Scissors),
+        )),
+        char(' '),
+        alt((
+            map(char('X'), |_| 0),
+            map(char('Y'), |_| 1),
+            map(char('Z'), |_| 2),
+        )),
     )(input)
 }
 
@@ -42,6 +87,11 @@ fn score(me: Play, outcome: Outcome) -> u32 {
     1 + me as u32 + 3 * outcome as u32
 }
 
+/// What the second column stands for in part 1
+const PLAYS: [Play; 3] = [Play::Rock, Play::Paper, Play::Scissors];
+/// What the second column stands for in part 2
+const OUTCOMES: [Outcome; 3] = [Outcome::Lose, Outcome::Draw, Outcome::Win];
+
 #[derive(PartialEq, Clone, Copy)]
 enum Play {
     Rock,
@@ -49,6 +99,7 @@ enum Play {
     Scissors,
 }
 
+#[derive(Clone, Copy)]
 enum Outcome {
     Lose,
     Draw,
@@ -94,12 +145,49 @@ impl Round2 {
     }
 }
 
-#[test]
-fn both_parts() {
-    let example = r#"A Y
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"A Y
 B X
 C Z
-"#;
-    assert_eq!(day02(example).0, 15);
-    assert_eq!(day02(example).1, 12);
+"#,
+    params: Params::NONE,
+    part1: Some("15"),
+    part2: Some("12"),
+}];
+
+pub struct Day02;
+
+impl Solution for Day02 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "Rock Paper Scissors"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        false
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day02", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        _bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        _params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        run_phases(parts, phase, || parse(input), part1, part2)
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
+}
+
+#[test]
+fn both_parts() {
+    crate::check_examples(&Day02);
 }
@@ -1,47 +1,90 @@
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+use core::fmt::Debug;
+
 use nom::character::complete::*;
-use nom::combinator::*;
-use nom::multi::*;
-use nom::sequence::*;
 
 use crate::bitset::U64Set;
+use crate::nom_extra::fold_lines;
+use crate::parse::{parse_all, Error, Expected, IResult, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
 
-#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day03(input: &str) -> (u32, u32) {
-    fold_many0(
-        terminated(Rucksack::parse, line_ending),
-        || ((0, 0), heapless::Vec::<_, 3>::new()),
-        |((mut part1, mut part2), mut window), rucksack| {
-            part1 += rucksack.item_in_both_priority().unwrap() as u32;
+const DAY: u8 = 3;
 
-            window.push(rucksack).unwrap();
+pub fn day03<A: Allocator + Debug + Copy>(
+    alloc: A,
+    input: &str,
+) -> Result<Answers<'static>, ParseError> {
+    let model = parse(alloc, input)?;
 
-            if window.len() == 3 {
-                part2 += badge(window.as_slice()).unwrap() as u32;
-                window.clear();
-            }
+    Ok((part1(&model), part2(&model)))
+}
 
-            ((part1, part2), window)
+pub type Model<A> = Vec<Rucksack, A>;
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn parse<A: Allocator + Debug + Copy>(alloc: A, input: &str) -> Result<Model<A>, ParseError> {
+    let rucksacks = fold_lines(
+        Rucksack::parse,
+        || Vec::with_capacity_in(300, alloc),
+        |mut rucksacks, rucksack| {
+            rucksacks.push(rucksack);
+            rucksacks
         },
-    )(input.as_bytes())
-    .unwrap()
-    .1
-     .0
+    );
+
+    parse_all(DAY, input.as_bytes(), rucksacks)
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<A: Allocator>(rucksacks: &Model<A>) -> Answer<'static> {
+    rucksacks
+        .iter()
+        .filter_map(|rucksack| rucksack.item_in_both_priority())
+        .map(|priority| priority as u32)
+        .sum::<u32>()
+        .into()
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2<A: Allocator>(rucksacks: &Model<A>) -> Answer<'static> {
+    // No answer without groups of three elves sharing a badge
+    rucksacks
+        .chunks(3)
+        .map(|elves| (elves.len() == 3).then(|| badge(elves)).flatten())
+        .map(|badge| badge.map(|priority| priority as u32))
+        .sum::<Option<u32>>()
+        .map_or(Answer::None, Answer::from)
 }
 
 #[derive(Debug)]
-struct Rucksack {
+pub struct Rucksack {
     comp_1: U64Set,
     comp_2: U64Set,
 }
 
 impl Rucksack {
-    fn parse(input: &[u8]) -> nom::IResult<&[u8], Rucksack, ()> {
-        map(alpha0, |items: &[u8]| {
-            let (comp_1, comp_2) = items.split_at(items.len() / 2);
-            let comp_1 = U64Set::from_iter(comp_1.iter().copied().map(priority));
-            let comp_2 = U64Set::from_iter(comp_2.iter().copied().map(priority));
-            Rucksack { comp_1, comp_2 }
-        })(input)
+    /// A line of items, the same number in each compartment with an item in
+    /// both of them
+    fn parse(input: &[u8]) -> IResult<&[u8], Rucksack> {
+        let error = |expected| nom::Err::Error(Error { input, expected });
+        let (rest, items) = alpha1(input)?;
+
+        if items.len() % 2 != 0 {
+            return Err(error(Expected::Token("an even number of items")));
+        }
+
+        let (comp_1, comp_2) = items.split_at(items.len() / 2);
+        let comp_1 = U64Set::from_iter(comp_1.iter().copied().map(priority));
+        let comp_2 = U64Set::from_iter(comp_2.iter().copied().map(priority));
+        let rucksack = Rucksack { comp_1, comp_2 };
+
+        if rucksack.item_in_both_priority().is_none() {
+            return Err(error(Expected::Token("an item in both compartments")));
+        }
+
+        Ok((rest, rucksack))
     }
 
     fn item_in_both_priority(&self) -> Option<u8> {
@@ -68,15 +111,73 @@ fn badge(elves: &[Rucksack]) -> Option<u8> {
         .next()
 }
 
-#[test]
-fn both_parts() {
-    let example = r#"vJrwpWtwJgWrhcsFMMfFFhFp
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"vJrwpWtwJgWrhcsFMMfFFhFp
 jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
 PmmdzqPrVvPwwTWBwg
 wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
 ttgJtRGJQctTZtZT
 CrZsJsPPZsGzwwsLwLmpwMDw
-"#;
-    assert_eq!(day03(example).0, 157);
-    assert_eq!(day03(example).1, 70);
+"#,
+    params: Params::NONE,
+    part1: Some("157"),
+    part2: Some("70"),
+}];
+
+pub struct Day03;
+
+impl Solution for Day03 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "Rucksack Reorganization"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        true
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day03", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        _params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        run_phases(parts, phase, || parse(bump, input), part1, part2)
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
+}
+
+#[test]
+fn both_parts() {
+    crate::check_examples(&Day03);
+}
+
+#[test]
+fn incomplete_rucksacks() {
+    let bump = Bump::new();
+    let example = EXAMPLES[0].input;
+
+    // Cut in the middle of a line
+    assert!(parse(&bump, &example[..example.len() - 4]).is_err());
+    assert!(parse(&bump, "abcd\n").is_err());
+
+    let (part1, part2) = Day03
+        .solve_with(
+            &bump,
+            &example[..example.rfind("CrZ").unwrap()],
+            Parts::BOTH,
+            Params::NONE,
+        )
+        .unwrap();
+    assert_eq!(part1, Answer::Int(157 - 19));
+    assert_eq!(part2, Answer::None);
 }
@@ -1,59 +1,118 @@
-use core::ops::RangeInclusive;
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+use core::fmt::Debug;
 
 use nom::character::complete::*;
 use nom::combinator::*;
-use nom::multi::*;
 use nom::sequence::*;
 
+use crate::interval::Interval;
+use crate::parse::{fold_all, parse_all, IResult, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
+
+const DAY: u8 = 4;
+
+pub fn day04<A: Allocator + Debug + Copy>(
+    alloc: A,
+    input: &str,
+) -> Result<Answers<'static>, ParseError> {
+    let model = parse(alloc, input)?;
+
+    Ok((part1(&model), part2(&model)))
+}
+
+/// Sections assigned to each pair of elves
+pub type Model<A> = Vec<(Interval<u32>, Interval<u32>), A>;
+
 #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day04(input: &str) -> (u32, u32) {
-    fold_many0(
+pub fn parse<A: Allocator + Debug + Copy>(alloc: A, input: &str) -> Result<Model<A>, ParseError> {
+    let pairs = fold_all(
         terminated(parse_elf_pair, line_ending),
-        || (0, 0),
-        |(part1, part2), (elf1, elf2)| {
-            (
-                part1 + if full_overlap(&elf1, &elf2) { 1 } else { 0 },
-                part2 + if partial_overlap(&elf1, &elf2) { 1 } else { 0 },
-            )
+        || Vec::with_capacity_in(1000, alloc),
+        |mut pairs, pair| {
+            pairs.push(pair);
+            pairs
         },
-    )(input.as_bytes())
-    .unwrap()
-    .1
-}
-
-fn partial_overlap(a: &RangeInclusive<u32>, b: &RangeInclusive<u32>) -> bool {
-    let partial_a_in_b = b.contains(a.start()) || b.contains(a.end());
-    let partial_b_in_a = a.contains(b.start()) || a.contains(b.end());
+    );
 
-    partial_a_in_b || partial_b_in_a
+    parse_all(DAY, input.as_bytes(), pairs)
 }
 
-fn full_overlap(a: &RangeInclusive<u32>, b: &RangeInclusive<u32>) -> bool {
-    let a_in_b = b.contains(a.start()) && b.contains(a.end());
-    let b_in_a = a.contains(b.start()) && a.contains(b.end());
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<A: Allocator>(pairs: &Model<A>) -> Answer<'static> {
+    pairs
+        .iter()
+        .filter(|(elf1, elf2)| elf1.covers(elf2) || elf2.covers(elf1))
+        .count()
+        .into()
+}
 
-    a_in_b || b_in_a
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2<A: Allocator>(pairs: &Model<A>) -> Answer<'static> {
+    pairs
+        .iter()
+        .filter(|(elf1, elf2)| elf1.overlaps(elf2))
+        .count()
+        .into()
 }
 
-fn parse_range(input: &[u8]) -> nom::IResult<&[u8], RangeInclusive<u32>, ()> {
-    map(separated_pair(u32, char('-'), u32), |(from, to)| from..=to)(input)
+fn parse_range(input: &[u8]) -> IResult<&[u8], Interval<u32>> {
+    map(separated_pair(u32, char('-'), u32), |(from, to)| {
+        Interval::new(from, to)
+    })(input)
 }
 
-fn parse_elf_pair(
-    input: &[u8],
-) -> nom::IResult<&[u8], (RangeInclusive<u32>, RangeInclusive<u32>), ()> {
+fn parse_elf_pair(input: &[u8]) -> IResult<&[u8], (Interval<u32>, Interval<u32>)> {
     separated_pair(parse_range, char(','), parse_range)(input)
 }
 
-#[test]
-fn both_parts() {
-    let example = r#"2-4,6-8
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"2-4,6-8
 2-3,4-5
 5-7,7-9
 2-8,3-7
 6-6,4-6
 2-6,4-8
-"#;
-    assert_eq!(day04(example).0, 2);
-    assert_eq!(day04(example).1, 4);
+"#,
+    params: Params::NONE,
+    part1: Some("2"),
+    part2: Some("4"),
+}];
+
+pub struct Day04;
+
+impl Solution for Day04 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "Camp Cleanup"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        true
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day04", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        _params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        run_phases(parts, phase, || parse(bump, input), part1, part2)
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
+}
+
+#[test]
+fn both_parts() {
+    crate::check_examples(&Day04);
 }
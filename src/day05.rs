@@ -1,4 +1,3 @@
-use alloc::borrow::ToOwned;
 use alloc::vec::Vec;
 use bumpalo::collections::String;
 use core::alloc::Allocator;
@@ -13,34 +12,102 @@ use nom::combinator::*;
 use nom::multi::*;
 use nom::sequence::*;
 
+use crate::parse::{fold_all, parse_all, Expected, IResult, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Params, Parts, Phase, Solution};
+
+const DAY: u8 = 5;
+
+pub fn day05<'bump>(bump: &'bump Bump, input: &str) -> Result<Answers<'bump>, ParseError> {
+    let model = parse(bump, input)?;
+
+    Ok((part1(bump, &model), part2(bump, &model)))
+}
+
+/// The stacks as drawn, and the moves to apply to them
+pub struct Model<A: Allocator> {
+    stacks: Stacks<A>,
+    moves: Vec<Move, A>,
+}
+
 #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day05<'bump>(alloc: &'bump Bump, input: &str) -> (&'bump str, &'bump str) {
-    let (mut rest, stacks) = Stacks::parse(alloc, input.as_bytes()).unwrap();
+pub fn parse<A: Allocator + Copy>(alloc: A, input: &str) -> Result<Model<A>, ParseError> {
+    let model = parse_all(DAY, input.as_bytes(), |input| {
+        let (input, stacks) = Stacks::parse(alloc, input)?;
+
+        let (input, moves) = fold_all(
+            terminated(Move::parse, newline),
+            || Vec::with_capacity_in(512, alloc),
+            |mut moves, m| {
+                moves.push(m);
+                moves
+            },
+        )(input)?;
+
+        Ok((input, Model { stacks, moves }))
+    })?;
+
+    model.check_moves(alloc, input)?;
+
+    Ok(model)
+}
+
+impl<A: Allocator> Model<A> {
+    /// Make sure each move takes crates from a stack holding as many, and puts
+    /// them on a stack of the drawing
+    fn check_moves<B: Allocator>(&self, alloc: B, input: &str) -> Result<(), ParseError> {
+        let error =
+            |at: &str, expected| ParseError::at(DAY, input.as_bytes(), at.as_bytes(), expected);
+        let lines = input.lines().filter(|line| line.starts_with("move "));
 
-    let mut stacks1 = Stacks {
-        stacks: Vec::new_in(alloc),
-    };
-    stacks.clone_into(&mut stacks1);
+        // Both parts move the same number of crates between the same stacks
+        let mut heights = Vec::with_capacity_in(self.stacks.stacks.len(), alloc);
+        heights.extend(self.stacks.stacks.iter().map(|stack| stack.len()));
 
-    let mut stacks2 = stacks;
+        for (m, line) in self.moves.iter().zip(lines) {
+            let stack = |n: u32, before: &str| match (n as usize).checked_sub(1) {
+                Some(i) if i < heights.len() => Ok(i),
+                _ => Err(error(
+                    &line[line.find(before).unwrap() + before.len()..],
+                    Expected::Token("a stack of the drawing"),
+                )),
+            };
+            let (from, to) = (stack(m.from, " from ")?, stack(m.to, " to ")?);
 
-    while let Ok((r, m)) = terminated(Move::parse, newline)(rest) {
-        rest = r;
-        stacks1.apply1(&m);
-        stacks2.apply2(&m);
+            if m.count as usize > heights[from] {
+                return Err(error(
+                    &line["move ".len()..],
+                    Expected::Token("at most as many crates as on the stack"),
+                ));
+            }
+
+            heights[from] -= m.count as usize;
+            heights[to] += m.count as usize;
+        }
+
+        Ok(())
     }
+}
 
-    let mut part1 = String::new_in(alloc);
-    for stack in stacks1.stacks {
-        part1.push(char::from_u32(stack[stack.len() - 1] as u32).unwrap());
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<'bump, A: Allocator + Copy>(bump: &'bump Bump, model: &Model<A>) -> Answer<'bump> {
+    let mut stacks = model.stacks.clone();
+
+    for m in model.moves.iter() {
+        stacks.apply1(m);
     }
 
-    let mut part2 = String::new_in(alloc);
-    for stack in stacks2.stacks {
-        part2.push(char::from_u32(stack[stack.len() - 1] as u32).unwrap());
+    stacks.tops(bump)
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2<'bump, A: Allocator + Copy>(bump: &'bump Bump, model: &Model<A>) -> Answer<'bump> {
+    let mut stacks = model.stacks.clone();
+
+    for m in model.moves.iter() {
+        stacks.apply2(m);
     }
 
-    (part1.into_bump_str(), part2.into_bump_str())
+    stacks.tops(bump)
 }
 
 const MAX_STACKS: usize = 10;
@@ -51,7 +118,7 @@ struct Stacks<A: Allocator> {
 }
 
 impl<A: Allocator + Copy> Stacks<A> {
-    fn parse(alloc: A, input: &[u8]) -> nom::IResult<&[u8], Self, ()> {
+    fn parse(alloc: A, input: &[u8]) -> IResult<&[u8], Self> {
         terminated(
             map(
                 fold_many0(
@@ -82,6 +149,17 @@ impl<A: Allocator + Copy> Stacks<A> {
         )(input)
     }
 
+    /// Crates at the top of each stack, skipping the empty ones
+    fn tops<'bump>(&self, bump: &'bump Bump) -> Answer<'bump> {
+        let mut tops = String::with_capacity_in(self.stacks.len(), bump);
+
+        for &top in self.stacks.iter().filter_map(|stack| stack.last()) {
+            tops.push(top as char);
+        }
+
+        Answer::Str(tops.into_bump_str())
+    }
+
     fn apply1(&mut self, m: &Move) {
         for _ in 0..m.count {
             let x = self.stacks[m.from as usize - 1].pop().unwrap();
@@ -118,7 +196,7 @@ impl<A: Allocator + Copy> Stacks<A> {
 
 type Row = ([Option<u8>; MAX_STACKS], usize);
 
-fn row_parse(input: &[u8]) -> nom::IResult<&[u8], Row, ()> {
+fn row_parse(input: &[u8]) -> IResult<&[u8], Row> {
     fold_many0(
         terminated(
             alt((
@@ -145,7 +223,7 @@ struct Move {
 }
 
 impl Move {
-    fn parse(input: &[u8]) -> nom::IResult<&[u8], Move, ()> {
+    fn parse(input: &[u8]) -> IResult<&[u8], Move> {
         map(
             tuple((
                 preceded(tag("move "), u32),
@@ -157,10 +235,8 @@ impl Move {
     }
 }
 
-#[test]
-fn both_parts() {
-    let bump = bumpalo::Bump::new();
-    let example = r#"    [D]    
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"    [D]    
 [N] [C]    
 [Z] [M] [P]
  1   2   3 
@@ -169,7 +245,70 @@ move 1 from 2 to 1
 move 3 from 1 to 3
 move 2 from 2 to 1
 move 1 from 1 to 2
-"#;
-    assert_eq!(day05(&bump, example).0, "CMZ");
-    assert_eq!(day05(&bump, example).1, "MCD");
+"#,
+    params: Params::NONE,
+    part1: Some("CMZ"),
+    part2: Some("MCD"),
+}];
+
+pub struct Day05;
+
+impl Solution for Day05 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "Supply Stacks"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        true
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day05", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        _params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        run_phases(
+            parts,
+            phase,
+            || parse(bump, input),
+            |model| part1(bump, model),
+            |model| part2(bump, model),
+        )
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
+}
+
+#[test]
+fn both_parts() {
+    crate::check_examples(&Day05);
+}
+
+#[test]
+fn invalid_moves() {
+    let bump = Bump::new();
+    let example = EXAMPLES[0].input;
+
+    for (from, to) in [
+        ("move 1 from 2 to 1", "move 1 from 9 to 1"),
+        ("move 1 from 2 to 1", "move 1 from 2 to 0"),
+        ("move 3 from 1 to 3", "move 5 from 1 to 3"),
+    ] {
+        assert!(parse(&bump, &example.replace(from, to)).is_err(), "{to}");
+    }
+
+    // Emptying a stack is fine
+    let emptied = example.replace("move 1 from 1 to 2", "move 2 from 1 to 2");
+    let answers = Day05.solve_with(&bump, &emptied, Parts::BOTH, Params::NONE);
+    assert_eq!(answers.unwrap(), (Answer::Str("CZ"), Answer::Str("CD")));
 }
@@ -1,33 +1,154 @@
 use crate::bitset::U32Set;
+use crate::parse::{Expected, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Param, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
+
+const DAY: u8 = 6;
+
+pub fn day06(input: &str) -> Result<Answers<'static>, ParseError> {
+    let signal = parse(input)?;
+    let config = Config::default();
+
+    Ok((part1(signal, &config), part2(signal, &config)))
+}
+
+crate::day_config! {
+    /// Distinct characters making a start-of-packet marker
+    packet_marker: usize = 4, in 1..=26;
+    /// Distinct characters making a start-of-message marker
+    message_marker: usize = 14, in 1..=26;
+}
+
+/// The signal, made of lowercase letters only
+pub type Model<'a> = &'a [u8];
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn parse(input: &str) -> Result<Model, ParseError> {
+    let signal = input.strip_suffix('\n').unwrap_or(input).as_bytes();
+
+    if let Some(c) = signal.iter().find(|c| !c.is_ascii_lowercase()) {
+        return Err(ParseError::at(
+            DAY,
+            input.as_bytes(),
+            core::slice::from_ref(c),
+            Expected::Token("a lowercase letter"),
+        ));
+    }
+
+    Ok(signal)
+}
 
 #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day06(input: &str) -> (usize, usize) {
-    let solve = |window_size| {
-        let (offset, _) = input
-            .as_bytes()
-            .windows(window_size)
-            .enumerate()
-            .find(|(_, w)| {
-                U32Set::insert_only_new(&mut U32Set::empty(), w.iter().map(|c| c - b'a'))
-            })
-            .unwrap();
-        offset + window_size
-    };
-
-    (solve(4), solve(14))
+pub fn part1(signal: Model, config: &Config) -> Answer<'static> {
+    find_marker(signal, config.packet_marker)
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2(signal: Model, config: &Config) -> Answer<'static> {
+    find_marker(signal, config.message_marker)
+}
+
+/// Number of characters read once `window_size` distinct ones have been, no
+/// answer when there's no such marker
+fn find_marker(signal: Model, window_size: usize) -> Answer<'static> {
+    signal
+        .windows(window_size)
+        .position(|w| U32Set::insert_only_new(&mut U32Set::empty(), w.iter().map(|c| c - b'a')))
+        .map_or(Answer::None, |offset| (offset + window_size).into())
+}
+
+const EXAMPLES: &[Example] = &[
+    Example {
+        input: "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+        params: Params::NONE,
+        part1: Some("7"),
+        part2: Some("19"),
+    },
+    Example {
+        input: "bvwbjplbgvbhsrlpgdmjqwftvncz",
+        params: Params::NONE,
+        part1: Some("5"),
+        part2: Some("23"),
+    },
+    Example {
+        input: "nppdvjthqldpwncqszvftbrmjlhg",
+        params: Params::NONE,
+        part1: Some("6"),
+        part2: Some("23"),
+    },
+    Example {
+        input: "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg",
+        params: Params::NONE,
+        part1: Some("10"),
+        part2: Some("29"),
+    },
+    Example {
+        input: "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw",
+        params: Params::NONE,
+        part1: Some("11"),
+        part2: Some("26"),
+    },
+];
+
+pub struct Day06;
+
+impl Solution for Day06 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "Tuning Trouble"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        false
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day06", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        _bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        let config = Config::from_params(DAY, params)?;
+
+        run_phases(
+            parts,
+            phase,
+            || parse(input),
+            |&signal| part1(signal, &config),
+            |&signal| part2(signal, &config),
+        )
+    }
+
+    fn params(&self) -> &'static [Param] {
+        Config::PARAMS
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
 }
 
 #[test]
 fn both_parts() {
-    assert_eq!(day06("mjqjpqmgbljsphdztnvjfqwrcgsmlb").0, 7);
-    assert_eq!(day06("bvwbjplbgvbhsrlpgdmjqwftvncz").0, 5);
-    assert_eq!(day06("nppdvjthqldpwncqszvftbrmjlhg").0, 6);
-    assert_eq!(day06("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg").0, 10);
-    assert_eq!(day06("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw").0, 11);
-
-    assert_eq!(day06("mjqjpqmgbljsphdztnvjfqwrcgsmlb").1, 19);
-    assert_eq!(day06("bvwbjplbgvbhsrlpgdmjqwftvncz").1, 23);
-    assert_eq!(day06("nppdvjthqldpwncqszvftbrmjlhg").1, 23);
-    assert_eq!(day06("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg").1, 29);
-    assert_eq!(day06("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw").1, 26);
+    crate::check_examples(&Day06);
+}
+
+#[test]
+fn out_of_range_params() {
+    let bump = Bump::new();
+    let params = Params(&[("message_marker", 27)]);
+    let err = Day06
+        .solve_with(&bump, "abcd", Parts::BOTH, params)
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "day 6: expected message_marker within 1..=26"
+    );
 }
@@ -9,37 +9,66 @@ use nom::branch::*;
 use nom::bytes::complete::*;
 use nom::character::complete::*;
 use nom::combinator::*;
-use nom::multi::*;
 use nom::sequence::*;
 
+use crate::parse::{fold_all, parse_all, IResult, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Param, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
+
+const DAY: u8 = 7;
+
+pub fn day07<A: Allocator + Copy + Debug>(
+    alloc: A,
+    input: &str,
+) -> Result<Answers<'static>, ParseError> {
+    let fs = parse(alloc, input)?;
+
+    Ok((part1(&fs), part2(&fs, &Config::default())))
+}
+
+crate::day_config! {
+    disk_size: u64 = 70000000, in 1..=i64::MAX;
+    /// Unused space needed to run the update
+    space_needed: u64 = 30000000, in 0..=i64::MAX;
+}
+
+/// Sizes of the directories, rebuilt from the terminal output
+pub type Model<'a, A> = FileSystem<'a, A>;
+
 #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day07<A: Allocator + Copy + Debug>(alloc: A, input: &str) -> (u64, u64) {
-    let fs = FileSystem::from_cli_input(alloc, input);
+pub fn parse<A: Allocator + Copy + Debug>(
+    alloc: A,
+    input: &str,
+) -> Result<Model<'_, A>, ParseError> {
+    FileSystem::from_cli_input(alloc, input)
+}
 
-    let part1 = fs
-        .0
-        .filter_map_reduce_dir_sizes(&|x| if x > 100000 { None } else { Some(x) }, &|a, b| a + b)
-        .unwrap();
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<A: Allocator + Copy>(fs: &Model<A>) -> Answer<'static> {
+    // No answer without a small enough directory
+    fs.0.filter_map_reduce_dir_sizes(&|x| if x > 100000 { None } else { Some(x) }, &|a, b| a + b)
+        .map_or(Answer::None, Answer::from)
+}
 
-    let total_disk_space = 70000000;
-    let need_unused = 30000000;
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2<A: Allocator + Copy>(fs: &Model<A>, config: &Config) -> Answer<'static> {
+    let total_disk_space = config.disk_size;
+    let need_unused = config.space_needed;
     let fs_size = fs.size();
 
-    let part2 =
-        fs.0.filter_map_reduce_dir_sizes(
-            &|x| {
-                let dir_big_enough = total_disk_space - fs_size + x >= need_unused;
-                if dir_big_enough {
-                    Some(x)
-                } else {
-                    None
-                }
-            },
-            &|a, b| a.min(b),
-        )
-        .unwrap();
-
-    (part1, part2)
+    // No answer when even deleting everything doesn't free enough space
+    fs.0.filter_map_reduce_dir_sizes(
+        &|x| {
+            let dir_big_enough = total_disk_space + x >= need_unused + fs_size;
+            if dir_big_enough {
+                Some(x)
+            } else {
+                None
+            }
+        },
+        &|a, b| a.min(b),
+    )
+    .map_or(Answer::None, Answer::from)
 }
 
 #[derive(Debug, PartialEq)]
@@ -50,7 +79,7 @@ enum Command<'a> {
 }
 
 impl<'a> Command<'a> {
-    fn parse(input: &'a str) -> nom::IResult<&'a str, Command<'a>, ()> {
+    fn parse(input: &'a str) -> IResult<&'a str, Command<'a>> {
         preceded(
             tag("$ "),
             alt((
@@ -69,7 +98,7 @@ enum Output<'a> {
 }
 
 impl<'a> Output<'a> {
-    fn parse(input: &'a str) -> nom::IResult<&'a str, Output<'a>, ()> {
+    fn parse(input: &'a str) -> IResult<&'a str, Output<'a>> {
         alt((
             map(preceded(tag("dir "), not_line_ending), Output::Dir),
             map(
@@ -87,7 +116,7 @@ enum Cli<'a> {
 }
 
 impl<'a> Cli<'a> {
-    fn parse(input: &'a str) -> nom::IResult<&'a str, Cli<'a>, ()> {
+    fn parse(input: &'a str) -> IResult<&'a str, Cli<'a>> {
         alt((
             map(Command::parse, Cli::Command),
             map(Output::parse, Cli::Output),
@@ -96,21 +125,20 @@ impl<'a> Cli<'a> {
 }
 
 #[derive(Debug)]
-struct FileSystem<'a, A: Allocator + Copy>(Node<'a, A>);
+pub struct FileSystem<'a, A: Allocator + Copy>(Node<'a, A>);
 
 impl<'a, A: Allocator + Copy> FileSystem<'a, A> {
-    fn from_cli_input(alloc: A, input: &'a str) -> FileSystem<'a, A> {
-        fold_many1(
+    fn from_cli_input(alloc: A, input: &'a str) -> Result<FileSystem<'a, A>, ParseError> {
+        let discover = fold_all(
             terminated(Cli::parse, newline),
             || (FileSystem::new(alloc), Vec::new_in(alloc)),
             |(mut fs, mut current), cli| {
                 fs.discover_step(&mut current, cli);
                 (fs, current)
             },
-        )(input)
-        .unwrap()
-        .1
-         .0
+        );
+
+        Ok(parse_all(DAY, input, discover)?.0)
     }
 
     fn new(alloc: A) -> FileSystem<'a, A> {
@@ -195,17 +223,8 @@ impl<'a, A: Allocator + Copy> Node<'a, A> {
     }
 }
 
-#[test]
-fn parse() {
-    assert_eq!(Command::parse("$ cd .."), Ok(("", Command::CdUp)));
-    assert_eq!(Command::parse("$ cd /"), Ok(("", Command::CdDown("/"))));
-    assert_eq!(Command::parse("$ ls"), Ok(("", Command::Ls)));
-}
-
-#[test]
-fn both_parts() {
-    let bump = bumpalo::Bump::new();
-    let example = r#"$ cd /
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"$ cd /
 $ ls
 dir a
 14848514 b.txt
@@ -228,7 +247,82 @@ $ ls
 8033020 d.log
 5626152 d.ext
 7214296 k
-"#;
-    assert_eq!(day07(&bump, example).0, 95437);
-    assert_eq!(day07(&bump, example).1, 24933642);
+"#,
+    params: Params::NONE,
+    part1: Some("95437"),
+    part2: Some("24933642"),
+}];
+
+pub struct Day07;
+
+impl Solution for Day07 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "No Space Left On Device"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        true
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day07", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        let config = Config::from_params(DAY, params)?;
+
+        run_phases(
+            parts,
+            phase,
+            || parse(bump, input),
+            part1,
+            |fs| part2(fs, &config),
+        )
+    }
+
+    fn params(&self) -> &'static [Param] {
+        Config::PARAMS
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
+}
+
+#[test]
+fn commands() {
+    assert_eq!(Command::parse("$ cd .."), Ok(("", Command::CdUp)));
+    assert_eq!(Command::parse("$ cd /"), Ok(("", Command::CdDown("/"))));
+    assert_eq!(Command::parse("$ ls"), Ok(("", Command::Ls)));
+}
+
+#[test]
+fn both_parts() {
+    crate::check_examples(&Day07);
+}
+
+#[test]
+fn malformed() {
+    use crate::parse::Expected;
+
+    let bump = bumpalo::Bump::new();
+    let example = "$ cd /\n$ ls\ndir a\n14848514 b.txt\n$ cd a\n$ cp a b\n";
+
+    assert_eq!(
+        day07(&bump, example),
+        Err(ParseError {
+            day: DAY,
+            line: 6,
+            column: 3,
+            expected: Expected::Kind(nom::error::ErrorKind::Tag),
+        })
+    );
 }
@@ -1,171 +1,167 @@
-use alloc::vec::Vec;
 use core::alloc::Allocator;
 use core::fmt::Debug;
+use core::mem::replace;
+
+use crate::grid::Grid;
+use crate::parse::{byte, parse_all, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
+
+const DAY: u8 = 8;
+
+pub fn day08<A: Allocator + Debug + Copy>(
+    alloc: A,
+    input: &str,
+) -> Result<Answers<'static>, ParseError> {
+    let forest = parse(alloc, input)?;
+
+    Ok((part1(alloc, &forest), part2(&forest)))
+}
+
+/// The tree heights, from a digit for each tree
+pub type Model<A> = Grid<i8, A>;
 
 #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day08<A: Allocator + Debug>(alloc: A, input: &str) -> (usize, usize) {
-    // Assume input is a square
-    let dim = input.find('\n').unwrap();
+pub fn parse<A: Allocator + Copy>(alloc: A, input: &str) -> Result<Model<A>, ParseError> {
+    let height = byte("a digit", |c| (c as char).to_digit(10).map(|h| h as i8));
 
-    let input = input.as_bytes();
+    parse_all(DAY, input.as_bytes(), Grid::parser(alloc, height))
+}
 
-    macro_rules! get_tree_height {
-        ($x:expr, $y:expr) => {
-            (input[($y * (dim + 1) + $x) as usize] - b'0') as i8
-        };
-    }
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<A: Allocator, B: Allocator>(alloc: A, forest: &Model<B>) -> Answer<'static> {
+    let (width, height) = (forest.width(), forest.height());
+    let mut visible = Grid::new_in(width, height, false, alloc);
+    let mut visible_count: usize = 0;
 
-    let mut visible_count = 0;
+    // Mark the trees taller than the tallest one seen so far from an edge
+    let mut look = |(x, y), tallest: &mut i8| {
+        let h = forest[(x, y)];
 
-    let mut visible = Vec::new_in(&alloc);
-    visible.resize(dim * dim, false);
+        if h > *tallest {
+            *tallest = h;
 
-    macro_rules! set_visible {
-        ($x:expr, $y:expr) => {
-            if let Some(vis) = visible.get_mut($y * dim + $x) {
-                if !*vis {
-                    *vis = true;
-                    visible_count += 1;
-                }
+            if !replace(&mut visible[(x, y)], true) {
+                visible_count += 1;
             }
-        };
-    }
-
-    let mut max_score = 0;
+        }
+    };
 
-    for y in 0..dim {
-        let mut tallest_up = -1;
-        let mut tallest_down = -1;
+    for y in 0..height {
         let mut tallest_left = -1;
         let mut tallest_right = -1;
-        for x in 0..dim {
-            // Visibility
-            {
-                // Left - traverse from left edge
-                let h = get_tree_height!(x, y);
-
-                if h > tallest_left {
-                    set_visible!(x, y);
-                    tallest_left = h;
-                }
-            }
 
-            {
-                // Right - traverse from right edge
-                let x = dim - 1 - x;
-                let h = get_tree_height!(x, y);
+        for x in 0..width {
+            look((x, y), &mut tallest_left);
+            look((width - 1 - x, y), &mut tallest_right);
+        }
+    }
 
-                if h > tallest_right {
-                    set_visible!(x, y);
-                    tallest_right = h;
-                }
-            }
+    for x in 0..width {
+        let mut tallest_up = -1;
+        let mut tallest_down = -1;
+
+        for y in 0..height {
+            look((x, y), &mut tallest_up);
+            look((x, height - 1 - y), &mut tallest_down);
+        }
+    }
 
-            {
-                // Up - traverse from top edge
-                let (x, y) = (y, x);
-                let h = get_tree_height!(x, y);
+    visible_count.into()
+}
 
-                if h > tallest_up {
-                    set_visible!(x, y);
-                    tallest_up = h;
-                }
-            }
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2<A: Allocator>(forest: &Model<A>) -> Answer<'static> {
+    let (width, height) = (forest.width(), forest.height());
+    let mut max_score: usize = 0;
 
-            {
-                // Down - traverse from bottom edge
-                let (x, y) = (y, x);
-                let y = dim - 1 - y;
-                let h = get_tree_height!(x, y);
+    for y in 0..height {
+        for x in 0..width {
+            let h = forest[(x, y)];
 
-                if h > tallest_down {
-                    set_visible!(x, y);
-                    tallest_down = h;
-                }
-            }
+            let left = viewing_distance(forest, h, (0..x).rev().map(|x| (x, y)));
+            let right = viewing_distance(forest, h, (x + 1..width).map(|x| (x, y)));
+            let up = viewing_distance(forest, h, (0..y).rev().map(|y| (x, y)));
+            let down = viewing_distance(forest, h, (y + 1..height).map(|y| (x, y)));
 
-            // Score
-            let h = get_tree_height!(x, y);
-            let mut tree_score = 1;
+            max_score = max_score.max(left * right * up * down);
+        }
+    }
 
-            {
-                // Left
-                let mut score = 0;
-                while x > score {
-                    score += 1;
-                    let next = x - score;
+    max_score.into()
+}
 
-                    if get_tree_height!(next, y) >= h {
-                        break;
-                    }
-                }
+/// Trees seen from a tree of height `h` along a line of trees, stopping at the
+/// first one at least as tall
+fn viewing_distance<A: Allocator>(
+    forest: &Model<A>,
+    h: i8,
+    line: impl Iterator<Item = (usize, usize)>,
+) -> usize {
+    let mut score = 0;
 
-                tree_score *= score;
-            }
+    for pos in line {
+        score += 1;
 
-            {
-                // Right
-                let mut score = 0;
-                while x < dim - 1 - score {
-                    score += 1;
-                    let next = x + score;
+        if forest[pos] >= h {
+            break;
+        }
+    }
 
-                    if get_tree_height!(next, y) >= h {
-                        break;
-                    }
-                }
+    score
+}
 
-                tree_score *= score;
-            }
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"30373
+25512
+65332
+33549
+35390
+"#,
+    params: Params::NONE,
+    part1: Some("21"),
+    part2: Some("8"),
+}];
 
-            {
-                // Up
-                let mut score = 0;
-                while y > score {
-                    score += 1;
-                    let next = y - score;
+pub struct Day08;
 
-                    if get_tree_height!(x, next) >= h {
-                        break;
-                    }
-                }
+impl Solution for Day08 {
+    fn day(&self) -> u8 {
+        DAY
+    }
 
-                tree_score *= score;
-            }
-            {
-                // Down
-                let mut score = 0;
-                while y < dim - 1 - score {
-                    score += 1;
-                    let next = y + score;
-
-                    if get_tree_height!(x, next) >= h {
-                        break;
-                    }
-                }
-
-                tree_score *= score;
-            }
+    fn title(&self) -> &'static str {
+        "Treetop Tree House"
+    }
 
-            max_score = max_score.max(tree_score);
-        }
+    fn needs_allocator(&self) -> bool {
+        true
     }
 
-    let part1 = visible_count;
-    let part2 = max_score;
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day08", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        _params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        run_phases(
+            parts,
+            phase,
+            || parse(bump, input),
+            |forest| part1(bump, forest),
+            part2,
+        )
+    }
 
-    (part1, part2)
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
 }
 
 #[test]
 fn both_parts() {
-    let bump = bumpalo::Bump::new();
-    let example = r#"30373
-25512
-65332
-33549
-35390
-"#;
-    assert_eq!(day08(&bump, example).0, 21);
-    assert_eq!(day08(&bump, example).1, 8);
+    crate::check_examples(&Day08);
 }
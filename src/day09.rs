@@ -1,3 +1,4 @@
+use alloc::vec::Vec;
 use core::alloc::Allocator;
 use core::cmp::Ordering;
 use core::fmt::Debug;
@@ -9,65 +10,96 @@ use nom::branch::*;
 use nom::bytes::complete::*;
 use nom::character::complete::*;
 use nom::combinator::*;
-use nom::multi::*;
 use nom::sequence::*;
 
+use crate::nom_extra::fold_lines;
+use crate::parse::{parse_all, IResult, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
+
+const DAY: u8 = 9;
+
+pub fn day09<A: Allocator + Copy + Debug>(
+    alloc: A,
+    input: &str,
+) -> Result<Answers<'static>, ParseError> {
+    let moves = parse(alloc, input)?;
+
+    Ok((part1(alloc, &moves), part2(alloc, &moves)))
+}
+
+/// Moves of the head of the rope
+pub type Model<A> = Vec<Move, A>;
+
 #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day09<A: Allocator + Debug>(alloc: A, input: &str) -> (usize, usize) {
-    let (visited1, visited9, _knots) = fold_many0(
-        terminated(Move::parse, newline),
-        || {
-            let mut visited1 = hash_set!(7000, &alloc);
-            visited1.insert((0, 0));
-            let mut visited9 = hash_set!(3000, &alloc);
-            visited9.insert((0, 0));
-            (visited1, visited9, [(0, 0); 10])
+pub fn parse<A: Allocator + Debug + Copy>(alloc: A, input: &str) -> Result<Model<A>, ParseError> {
+    let moves = fold_lines(
+        Move::parse,
+        || Vec::with_capacity_in(2000, alloc),
+        |mut moves, m| {
+            moves.push(m);
+            moves
         },
-        |(mut visited1, mut visited9, mut knots), m| {
-            for m in m.steps() {
-                knots[0] = m.apply_move(knots[0]);
-                let mut prev = knots[0];
-
-                for (i, k) in knots[1..].iter_mut().enumerate() {
-                    let needs_to_move = (prev.0 - k.0).abs() > 1 || (prev.1 - k.1).abs() > 1;
-
-                    if needs_to_move {
-                        k.0 = match prev.0.cmp(&k.0) {
-                            Ordering::Greater => k.0 + 1,
-                            Ordering::Less => k.0 - 1,
-                            Ordering::Equal => k.0,
-                        };
-
-                        k.1 = match prev.1.cmp(&k.1) {
-                            Ordering::Greater => k.1 + 1,
-                            Ordering::Less => k.1 - 1,
-                            Ordering::Equal => k.1,
-                        };
-
-                        if i == 0 {
-                            visited1.insert(*k);
-                        }
-
-                        if i == 8 {
-                            visited9.insert(*k);
-                        }
-                    }
-
-                    prev = *k;
-                }
+    );
+
+    parse_all(DAY, input, moves)
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<A: Allocator + Copy>(alloc: A, moves: &Model<A>) -> Answer<'static> {
+    tail_positions::<2, _>(alloc, moves, 7000).into()
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2<A: Allocator + Copy>(alloc: A, moves: &Model<A>) -> Answer<'static> {
+    tail_positions::<10, _>(alloc, moves, 3000).into()
+}
+
+/// Number of positions visited by the tail of a rope of `N` knots
+fn tail_positions<const N: usize, A: Allocator + Copy>(
+    alloc: A,
+    moves: &Model<A>,
+    capacity: usize,
+) -> usize {
+    let mut visited = hash_set!(capacity, alloc);
+    visited.insert((0, 0));
+    let mut knots = [(0, 0); N];
+
+    for m in moves.iter().flat_map(|m| m.steps()) {
+        knots[0] = m.apply_move(knots[0]);
+        let mut prev = knots[0];
+
+        for k in knots[1..].iter_mut() {
+            let needs_to_move = (prev.0 - k.0).abs() > 1 || (prev.1 - k.1).abs() > 1;
+
+            if !needs_to_move {
+                // The knots further down the rope don't move either
+                break;
             }
 
-            (visited1, visited9, knots)
-        },
-    )(input)
-    .unwrap()
-    .1;
+            k.0 = match prev.0.cmp(&k.0) {
+                Ordering::Greater => k.0 + 1,
+                Ordering::Less => k.0 - 1,
+                Ordering::Equal => k.0,
+            };
+
+            k.1 = match prev.1.cmp(&k.1) {
+                Ordering::Greater => k.1 + 1,
+                Ordering::Less => k.1 - 1,
+                Ordering::Equal => k.1,
+            };
+
+            prev = *k;
+        }
 
-    (visited1.len(), visited9.len())
+        visited.insert(knots[N - 1]);
+    }
+
+    visited.len()
 }
 
 #[derive(Debug, Clone, Copy)]
-enum Move {
+pub enum Move {
     Up(u8),
     Down(u8),
     Left(u8),
@@ -75,7 +107,7 @@ enum Move {
 }
 
 impl Move {
-    fn parse(input: &str) -> nom::IResult<&str, Move, ()> {
+    fn parse(input: &str) -> IResult<&str, Move> {
         alt((
             map(preceded(tag("U "), u8), Move::Up),
             map(preceded(tag("D "), u8), Move::Down),
@@ -103,10 +135,9 @@ impl Move {
     }
 }
 
-#[test]
-fn example1() {
-    let bump = bumpalo::Bump::new();
-    let example = r#"R 4
+const EXAMPLES: &[Example] = &[
+    Example {
+        input: r#"R 4
 U 4
 L 3
 D 1
@@ -114,15 +145,13 @@ R 4
 D 1
 L 5
 R 2
-"#;
-    assert_eq!(day09(&bump, example).0, 13);
-    assert_eq!(day09(&bump, example).1, 1);
-}
-
-#[test]
-fn example2() {
-    let bump = bumpalo::Bump::new();
-    let example = r#"R 5
+"#,
+        params: Params::NONE,
+        part1: Some("13"),
+        part2: Some("1"),
+    },
+    Example {
+        input: r#"R 5
 U 8
 L 8
 D 3
@@ -130,7 +159,52 @@ R 17
 D 10
 L 25
 U 20
-"#;
-    assert_eq!(day09(&bump, example).0, 88);
-    assert_eq!(day09(&bump, example).1, 36);
+"#,
+        params: Params::NONE,
+        part1: Some("88"),
+        part2: Some("36"),
+    },
+];
+
+pub struct Day09;
+
+impl Solution for Day09 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "Rope Bridge"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        true
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day09", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        _params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        run_phases(
+            parts,
+            phase,
+            || parse(bump, input),
+            |moves| part1(bump, moves),
+            |moves| part2(bump, moves),
+        )
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
+}
+
+#[test]
+fn both_parts() {
+    crate::check_examples(&Day09);
 }
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+use core::alloc::Allocator;
 use core::iter::once;
 use core::ops::RangeInclusive;
 
@@ -8,43 +10,76 @@ use nom::branch::*;
 use nom::bytes::complete::*;
 use nom::character::complete::*;
 use nom::combinator::*;
-use nom::multi::*;
 use nom::sequence::*;
 
-#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day10<'bump>(bump: &'bump Bump, input: &str) -> (usize, &'bump str) {
-    let (_cpu, part1, crt) = fold_many0(
-        terminated(Instruction::parse, newline),
-        || (Cpu::new(), 0, Crt::new()),
-        |(mut cpu, mut signal_strength, mut crt), instr| {
-            for state in cpu.steps(instr) {
-                let x = state.x;
+use crate::nom_extra::fold_lines;
+use crate::parse::{parse_all, IResult, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Params, Parts, Phase, Solution};
 
-                if state.cycle_count >= 20 && (state.cycle_count - 20) % 40 == 0 {
-                    signal_strength += state.cycle_count * x as usize;
-                }
+const DAY: u8 = 10;
 
-                let sprite = (x - 1)..=(x + 1);
+pub fn day10<'bump>(bump: &'bump Bump, input: &str) -> Result<Answers<'bump>, ParseError> {
+    let program = parse(bump, input)?;
 
-                crt.set(state.cycle_count, sprite);
-            }
+    Ok((part1(&program), part2(bump, &program)))
+}
 
-            (cpu, signal_strength, crt)
+/// The CPU's program
+pub type Model<A> = Vec<Instruction, A>;
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn parse<A: Allocator + Copy>(alloc: A, input: &str) -> Result<Model<A>, ParseError> {
+    let instructions = fold_lines(
+        Instruction::parse,
+        || Vec::with_capacity_in(200, alloc),
+        |mut instructions, instr| {
+            instructions.push(instr);
+            instructions
         },
-    )(input)
-    .unwrap()
-    .1;
+    );
+
+    parse_all(DAY, input, instructions)
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<A: Allocator>(program: &Model<A>) -> Answer<'static> {
+    run(program)
+        .filter(|state| state.cycle_count >= 20 && (state.cycle_count - 20) % 40 == 0)
+        .map(|state| state.cycle_count * state.x as usize)
+        .sum::<usize>()
+        .into()
+}
 
-    (part1, crt.render(bump))
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2<'bump, A: Allocator>(bump: &'bump Bump, program: &Model<A>) -> Answer<'bump> {
+    let mut crt = Crt::new();
+
+    // The CPU only yields its state after each cycle, draw the first
+    let cpu = Cpu::new();
+    crt.set(cpu.cycle_count, (cpu.x - 1)..=(cpu.x + 1));
+
+    for state in run(program) {
+        crt.set(state.cycle_count, (state.x - 1)..=(state.x + 1));
+    }
+
+    Answer::Art(crt.render(bump))
 }
 
-enum Instruction {
+/// State of the CPU after each cycle of the program
+fn run<A: Allocator>(program: &Model<A>) -> impl Iterator<Item = Cpu> + '_ {
+    let mut cpu = Cpu::new();
+
+    program.iter().flat_map(move |&instr| cpu.steps(instr))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Instruction {
     AddX(i32),
     NoOp,
 }
 
 impl Instruction {
-    fn parse(input: &str) -> nom::IResult<&str, Instruction, ()> {
+    fn parse(input: &str) -> IResult<&str, Instruction> {
         alt((
             map(preceded(tag("addx "), i32), Instruction::AddX),
             map(tag("noop"), |_| Instruction::NoOp),
@@ -146,10 +181,8 @@ impl Crt {
     }
 }
 
-#[test]
-fn both_parts() {
-    let bump = bumpalo::Bump::new();
-    let example = r#"addx 15
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"addx 15
 addx -11
 addx 6
 addx -3
@@ -295,6 +328,69 @@ addx -11
 noop
 noop
 noop
-"#;
-    assert_eq!(day10(&bump, example).0, 13140);
+"#,
+    params: Params::NONE,
+    part1: Some("13140"),
+    part2: Some(
+        r#"##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######....."#,
+    ),
+}];
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "Cathode-Ray Tube"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        true
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day10", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        _params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        run_phases(
+            parts,
+            phase,
+            || parse(bump, input),
+            part1,
+            |program| part2(bump, program),
+        )
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
+}
+
+#[test]
+fn both_parts() {
+    crate::check_examples(&Day10);
+}
+
+#[test]
+fn first_pixel() {
+    let bump = Bump::new();
+    let crt = day10(&bump, "addx -3\n").unwrap().1.to_string();
+    let mut rows = crt.lines();
+
+    // Drawn during the first cycle, before the CPU yields any state
+    assert_eq!(rows.next(), Some(format!("##{}", ".".repeat(38)).as_str()));
+    assert!(rows.all(|row| row == ".".repeat(CRT_WIDTH)));
 }
@@ -1,4 +1,3 @@
-use alloc::borrow::ToOwned;
 use alloc::vec::Vec;
 use core::alloc::Allocator;
 use core::cmp::Reverse;
@@ -11,71 +10,150 @@ use nom::combinator::*;
 use nom::multi::*;
 use nom::sequence::*;
 
-#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day11<A: Allocator + Debug>(alloc: A, input: &str) -> (usize, usize) {
-    let parse = tracing::trace_span!("parse");
-    let parse = parse.enter();
+use crate::parse::{fold_all, parse_all, Expected, IResult, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Param, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
+
+const DAY: u8 = 11;
+
+pub fn day11<A: Allocator + Copy + Debug>(
+    alloc: A,
+    input: &str,
+) -> Result<Answers<'static>, ParseError> {
+    let monkeys = parse(alloc, input)?;
+    let config = Config::default();
+
+    Ok((
+        part1(alloc, &monkeys, &config),
+        part2(alloc, &monkeys, &config),
+    ))
+}
+
+crate::day_config! {
+    /// Rounds of part 1, where worry levels are divided by 3
+    part1_rounds: usize = 20, in 0..=u32::MAX as i64;
+    /// Rounds of part 2, where worry levels aren't divided
+    part2_rounds: usize = 10_000, in 0..=u32::MAX as i64;
+}
 
-    let mut monkeys = fold_many0(
-        terminated(|i| Monkey::parse(&alloc, i), opt(line_ending)),
-        || Vec::with_capacity_in(8, &alloc),
+/// The monkeys, holding their starting items
+pub type Model<A> = Vec<Monkey<A>, A>;
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn parse<A: Allocator + Copy + Debug>(alloc: A, input: &str) -> Result<Model<A>, ParseError> {
+    let monkeys = fold_all(
+        terminated(|i| Monkey::parse(alloc, i), opt(line_ending)),
+        || Vec::with_capacity_in(8, alloc),
         |mut v, m| {
             v.push(m);
             v
         },
-    )(input)
-    .unwrap()
-    .1;
+    );
 
-    drop(parse);
+    let monkeys = parse_all(DAY, input, monkeys)?;
+    let error = |at: &str, expected| ParseError::at(DAY, input.as_bytes(), at.as_bytes(), expected);
 
-    let part1 = {
-        let part1 = tracing::trace_span!("part1");
-        let _part1 = part1.enter();
-
-        let mut monkeys_tmp = Vec::with_capacity_in(monkeys.len(), &alloc);
-        monkeys.clone_into(&mut monkeys_tmp);
-        let mut monkeys = monkeys_tmp;
+    if monkeys.len() < 2 {
+        return Err(error(
+            &input[input.len()..],
+            Expected::Token("at least two monkeys"),
+        ));
+    }
 
-        let mut count = Vec::with_capacity_in(monkeys.len(), &alloc);
-        count.resize(monkeys.len(), 0);
+    // Worry levels are taken modulo the divisors
+    let divisors = input
+        .match_indices("divisible by ")
+        .map(|(at, tag)| &input[at + tag.len()..]);
 
-        for _ in 0..20 {
-            for i in 0..monkeys.len() {
-                while let Some((j, item)) = monkeys[i].item_handle() {
-                    count[i] += 1;
-                    monkeys[j].items.push(item);
-                }
-            }
+    for (monkey, at) in monkeys.iter().zip(divisors) {
+        if monkey.test_num == 0 {
+            return Err(error(at, Expected::Token("a divisor other than 0")));
         }
+    }
 
-        count.sort_by_key(|x| Reverse(*x));
-        count[0] * count[1]
-    };
+    // The targets of the throws in the order of the input
+    let targets = monkeys
+        .iter()
+        .enumerate()
+        .flat_map(|(i, m)| [(i, m.test_true), (i, m.test_false)]);
+    let throws = input
+        .match_indices("throw to monkey ")
+        .map(|(at, tag)| &input[at + tag.len()..]);
+
+    for ((i, target), at) in targets.zip(throws) {
+        if target as usize >= monkeys.len() || target as usize == i {
+            return Err(error(at, Expected::Token("another monkey of the list")));
+        }
+    }
 
-    let part2 = {
-        let part2 = tracing::trace_span!("part2");
-        let _part2 = part2.enter();
+    Ok(monkeys)
+}
 
-        let mut count = Vec::with_capacity_in(monkeys.len(), &alloc);
-        count.resize(monkeys.len(), 0);
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<A: Allocator + Copy>(
+    alloc: A,
+    monkeys: &Model<A>,
+    config: &Config,
+) -> Answer<'static> {
+    let mut items = held_items(alloc, monkeys);
+
+    let mut count = Vec::with_capacity_in(monkeys.len(), alloc);
+    count.resize(monkeys.len(), 0usize);
+
+    for _ in 0..config.part1_rounds {
+        for (i, monkey) in monkeys.iter().enumerate() {
+            while let Some(item) = items[i].pop() {
+                let (j, item) = monkey.item_handle(item);
+                count[i] += 1;
+                items[j].push(item);
+            }
+        }
+    }
 
-        let modulo = monkeys.iter().map(|m| m.test_num).product();
+    count.sort_by_key(|x| Reverse(*x));
+    (count[0] * count[1]).into()
+}
 
-        for _ in 0..10_000 {
-            for i in 0..monkeys.len() {
-                while let Some((j, item)) = monkeys[i].item_handle_2(modulo) {
-                    count[i] += 1;
-                    monkeys[j].items.push(item);
-                }
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2<A: Allocator + Copy>(
+    alloc: A,
+    monkeys: &Model<A>,
+    config: &Config,
+) -> Answer<'static> {
+    let mut items = held_items(alloc, monkeys);
+
+    let mut count = Vec::with_capacity_in(monkeys.len(), alloc);
+    count.resize(monkeys.len(), 0usize);
+
+    let modulo = monkeys.iter().map(|m| m.test_num).product();
+
+    for _ in 0..config.part2_rounds {
+        for (i, monkey) in monkeys.iter().enumerate() {
+            while let Some(item) = items[i].pop() {
+                let (j, item) = monkey.item_handle_2(item, modulo);
+                count[i] += 1;
+                items[j].push(item);
             }
         }
+    }
+
+    count.sort_by_key(|x| Reverse(*x));
+    (count[0] * count[1]).into()
+}
+
+/// The items each monkey starts with, with room for all of them so that
+/// throwing them around doesn't reallocate
+fn held_items<A: Allocator + Copy>(alloc: A, monkeys: &Model<A>) -> Vec<Vec<u64, A>, A> {
+    let total = monkeys.iter().map(|m| m.items.len()).sum();
+    let mut items = Vec::with_capacity_in(monkeys.len(), alloc);
 
-        count.sort_by_key(|x| Reverse(*x));
-        count[0] * count[1]
-    };
+    for monkey in monkeys {
+        let mut held = Vec::with_capacity_in(total, alloc);
+        held.extend_from_slice(&monkey.items);
+        items.push(held);
+    }
 
-    (part1, part2)
+    items
 }
 
 #[derive(Debug, Clone)]
@@ -85,7 +163,7 @@ enum Operation {
 }
 
 impl Operation {
-    fn parse(input: &str) -> nom::IResult<&str, Self, ()> {
+    fn parse(input: &str) -> IResult<&str, Self> {
         map(
             preceded(
                 tag("new = old "),
@@ -123,7 +201,7 @@ impl Operation {
 }
 
 #[derive(Debug, Clone)]
-struct Monkey<A: Allocator> {
+pub struct Monkey<A: Allocator> {
     items: Vec<u64, A>,
     operation: Operation,
     test_num: u64,
@@ -132,7 +210,7 @@ struct Monkey<A: Allocator> {
 }
 
 impl<A: Allocator + Copy> Monkey<A> {
-    fn parse(alloc: A, input: &str) -> nom::IResult<&str, Self, ()> {
+    fn parse(alloc: A, input: &str) -> IResult<&str, Self> {
         let (input, _) = tuple((tag("Monkey "), digit1, tag(":"), line_ending))(input)?;
 
         let (input, items) = preceded(tag("  Starting items: "), |i| parse_items(alloc, i))(input)?;
@@ -167,31 +245,30 @@ impl<A: Allocator + Copy> Monkey<A> {
         ))
     }
 
-    fn item_handle(&mut self) -> Option<(usize, u64)> {
-        let item = self.items.pop()?;
+    /// The monkey to throw `item` to, and its new worry level
+    fn item_handle(&self, item: u64) -> (usize, u64) {
         let item = self.operation.apply(item);
         let item = item / 3;
 
         if item % self.test_num == 0 {
-            Some((self.test_true as usize, item))
+            (self.test_true as usize, item)
         } else {
-            Some((self.test_false as usize, item))
+            (self.test_false as usize, item)
         }
     }
 
-    fn item_handle_2(&mut self, m: u64) -> Option<(usize, u64)> {
-        let item = self.items.pop()?;
+    fn item_handle_2(&self, item: u64, m: u64) -> (usize, u64) {
         let item = self.operation.apply_modulo(item, m);
 
         if item % self.test_num == 0 {
-            Some((self.test_true as usize, item))
+            (self.test_true as usize, item)
         } else {
-            Some((self.test_false as usize, item))
+            (self.test_false as usize, item)
         }
     }
 }
 
-fn parse_items<A: Allocator + Copy>(alloc: A, input: &str) -> nom::IResult<&str, Vec<u64, A>, ()> {
+fn parse_items<A: Allocator + Copy>(alloc: A, input: &str) -> IResult<&str, Vec<u64, A>> {
     fold_many0(
         terminated(u64, alt((tag(", "), line_ending))),
         || Vec::with_capacity_in(64, alloc),
@@ -202,10 +279,8 @@ fn parse_items<A: Allocator + Copy>(alloc: A, input: &str) -> nom::IResult<&str,
     )(input)
 }
 
-#[test]
-fn both_parts() {
-    let bump = bumpalo::Bump::new();
-    let example = r#"Monkey 0:
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"Monkey 0:
   Starting items: 79, 98
   Operation: new = old * 19
   Test: divisible by 23
@@ -232,7 +307,74 @@ Monkey 3:
   Test: divisible by 17
     If true: throw to monkey 0
     If false: throw to monkey 1
-"#;
-    assert_eq!(day11(&bump, example).0, 10605);
-    assert_eq!(day11(&bump, example).1, 2713310158);
+"#,
+    params: Params::NONE,
+    part1: Some("10605"),
+    part2: Some("2713310158"),
+}];
+
+pub struct Day11;
+
+impl Solution for Day11 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "Monkey in the Middle"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        true
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day11", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        let config = Config::from_params(DAY, params)?;
+
+        run_phases(
+            parts,
+            phase,
+            || parse(bump, input),
+            |monkeys| part1(bump, monkeys, &config),
+            |monkeys| part2(bump, monkeys, &config),
+        )
+    }
+
+    fn params(&self) -> &'static [Param] {
+        Config::PARAMS
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
+}
+
+#[test]
+fn both_parts() {
+    crate::check_examples(&Day11);
+}
+
+#[test]
+fn invalid_monkeys() {
+    let bump = Bump::new();
+    let example = EXAMPLES[0].input;
+    let first = &example[..example.find("\n\n").unwrap() + 1];
+
+    assert!(parse(&bump, "").is_err());
+    assert!(parse(&bump, first).is_err());
+    assert!(parse(&bump, &example.replace("monkey 3", "monkey 7")).is_err());
+    assert!(parse(
+        &bump,
+        &example.replace("If false: throw to monkey 0", "If false: throw to monkey 1")
+    )
+    .is_err());
+    assert!(parse(&bump, &example.replace("divisible by 19", "divisible by 0")).is_err());
 }
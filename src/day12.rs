@@ -1,109 +1,219 @@
+use alloc::collections::VecDeque;
 use core::alloc::Allocator;
+use core::cell::Cell;
 use core::fmt::Debug;
 
-use crate::hash_map;
-use crate::hash_set;
-use heapless::binary_heap::Min;
-use heapless::BinaryHeap;
+use crate::grid::Grid;
+use crate::parse::{byte, parse_all, Expected, ParseError};
+use crate::pathfinding::{bfs, Graph, NoPath};
+use crate::{run_phases, Answer, Answers, Example, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
 
-#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day12<A: Allocator + Debug>(alloc: A, input: &str) -> (u16, u16) {
-    let input = input.as_bytes();
+const DAY: u8 = 12;
+
+pub fn day12<A: Allocator + Debug + Copy>(
+    alloc: A,
+    input: &str,
+) -> Result<Answers<'static>, ParseError> {
+    let hill = parse(alloc, input)?;
+
+    Ok((part1(alloc, &hill), part2(alloc, &hill)))
+}
+
+/// The heightmap, with the start and end positions, and the fewest steps to
+/// the end once searched
+#[derive(Debug, Clone)]
+pub struct Model<A: Allocator> {
+    altitudes: Grid<u8, A>,
+    start: (usize, usize),
+    end: (usize, usize),
+    steps: Cell<Option<Steps>>,
+}
 
-    let mut width = None;
-    let mut start = None;
-    let mut end = None;
+/// Fewest steps to climb to the end, if it can be reached
+#[derive(Debug, Clone, Copy)]
+struct Steps {
+    from_start: Option<u16>,
+    from_lowest: Option<u16>,
+}
 
-    for (y, line) in input.split(|c| *c == b'\n').enumerate() {
-        if width.is_none() {
-            width = Some(line.len())
+impl<A: Allocator> Model<A> {
+    /// Fewest steps to the end from the start and from the lowest positions,
+    /// both found by a single search in reverse from the end
+    fn steps<B: Allocator + Copy>(&self, alloc: B) -> Steps {
+        if let Some(steps) = self.steps.get() {
+            return steps;
         }
 
-        for (x, c) in line.iter().enumerate() {
-            match c {
-                b'S' => start = Some((x as u8, y as u8)),
-                b'E' => end = Some((x as u8, y as u8)),
-                _ => {}
+        let (width, height) = (self.altitudes.width(), self.altitudes.height());
+        let mut costs = Grid::new_in(width, height, u16::MAX, alloc);
+        let mut to_visit = VecDeque::with_capacity_in(64, alloc);
+
+        let from_start = bfs(
+            self,
+            self.end,
+            |pos| pos == self.start,
+            &mut costs,
+            &mut to_visit,
+            &mut NoPath,
+        )
+        .map(|(steps, _)| steps);
+
+        // The start is one of the lowest positions, so the closest of them
+        // were reached before it and have their fewest steps in `costs`
+        let from_lowest = costs
+            .cells()
+            .iter()
+            .zip(self.altitudes.cells())
+            .filter(|&(_, &altitude)| altitude == 0)
+            .map(|(&steps, _)| steps)
+            .min()
+            .filter(|&steps| steps != u16::MAX);
+
+        let steps = Steps {
+            from_start,
+            from_lowest,
+        };
+        self.steps.set(Some(steps));
+
+        steps
+    }
+}
+
+/// The climb in reverse, each step going at most one lower
+impl<A: Allocator> Graph for Model<A> {
+    type Node = (usize, usize);
+    type Cost = u16;
+
+    fn successors(&self, (x, y): (usize, usize), mut visit: impl FnMut((usize, usize), u16)) {
+        let altitudes = &self.altitudes;
+        let c = altitudes[(x, y)];
+
+        for n in altitudes.neighbours4(x, y) {
+            if c <= altitudes[n] + 1 {
+                visit(n, 1);
             }
         }
     }
+}
 
-    let width = width.unwrap();
-    let start = start.unwrap();
-    let end = end.unwrap();
-
-    let altitude = |(x, y): (u8, u8)| {
-        if x as usize >= width {
-            None
-        } else {
-            input
-                .get(y as usize * (width + 1) + x as usize)
-                .map(|c| match c {
-                    b'S' => 0,
-                    b'E' => 25,
-                    _ => c - b'a',
-                })
-        }
-    };
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn parse<A: Allocator + Copy>(alloc: A, input: &str) -> Result<Model<A>, ParseError> {
+    let input = input.as_bytes();
+    let square = byte("a lowercase letter, 'S' or 'E'", |c| match c {
+        b'S' | b'E' | b'a'..=b'z' => Some(c),
+        _ => None,
+    });
+
+    let mut altitudes = parse_all(DAY, input, Grid::parser(alloc, square))?;
+
+    let eof = &input[input.len()..];
+    let start = altitudes
+        .position(|&c| c == b'S')
+        .ok_or_else(|| ParseError::at(DAY, input, eof, Expected::Char('S')))?;
+    let end = altitudes
+        .position(|&c| c == b'E')
+        .ok_or_else(|| ParseError::at(DAY, input, eof, Expected::Char('E')))?;
+
+    for c in altitudes.cells_mut() {
+        *c = match c {
+            b'S' => 0,
+            b'E' => 25,
+            _ => *c - b'a',
+        };
+    }
 
-    // Search in reverse, in order to solve part2 along the way
-    let mut to_visit = BinaryHeap::<_, Min, 32>::new();
-    to_visit.push((0, end)).unwrap();
+    Ok(Model {
+        altitudes,
+        start,
+        end,
+        steps: Cell::new(None),
+    })
+}
 
-    let mut costs = hash_map!(width * width, &alloc);
-    costs.insert(end, 0);
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<A: Allocator + Copy, B: Allocator>(alloc: A, hill: &Model<B>) -> Answer<'static> {
+    hill.steps(alloc)
+        .from_start
+        .map_or(Answer::None, Answer::from)
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2<A: Allocator + Copy, B: Allocator>(alloc: A, hill: &Model<B>) -> Answer<'static> {
+    hill.steps(alloc)
+        .from_lowest
+        .map_or(Answer::None, Answer::from)
+}
 
-    let mut visited = hash_set!(width * width, &alloc);
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"Sabqponm
+abcryxxl
+accszExk
+acctuvwj
+abdefghi
+"#,
+    params: Params::NONE,
+    part1: Some("31"),
+    part2: Some("29"),
+}];
 
-    let mut part1 = None;
-    let mut part2 = None;
+pub struct Day12;
 
-    while let Some((cost, current)) = to_visit.pop() {
-        visited.insert(current);
+impl Solution for Day12 {
+    fn day(&self) -> u8 {
+        DAY
+    }
 
-        if current == start {
-            part1 = Some(cost);
-            break;
-        }
+    fn title(&self) -> &'static str {
+        "Hill Climbing Algorithm"
+    }
 
-        if part2.is_none() && altitude(current) == Some(0) {
-            part2 = Some(cost);
-        }
+    fn needs_allocator(&self) -> bool {
+        true
+    }
 
-        let (x, y) = current;
-        let x = x as isize;
-        let y = y as isize;
-
-        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
-            if x + dx >= 0 && y + dy >= 0 {
-                let n = ((x + dx) as u8, (y + dy) as u8);
-                let c = altitude(current).unwrap();
-                match altitude(n) {
-                    Some(a) if c <= a + 1 => match costs.get(&n) {
-                        Some(&prev) if cost + 1 >= prev => {}
-                        _ => {
-                            to_visit.push((cost + 1, n)).unwrap();
-                            costs.insert(n, cost + 1);
-                        }
-                    },
-                    _ => {}
-                }
-            }
-        }
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day12", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        _params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        run_phases(
+            parts,
+            phase,
+            || parse(bump, input),
+            |hill| part1(bump, hill),
+            |hill| part2(bump, hill),
+        )
     }
 
-    (part1.unwrap(), part2.unwrap())
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
 }
 
 #[test]
 fn both_parts() {
-    let bump = bumpalo::Bump::new();
-    let example = r#"Sabqponm
-abcryxxl
-accszExk
-acctuvwj
-abdefghi
-"#;
-    assert_eq!(day12(&bump, example).0, 31);
-    assert_eq!(day12(&bump, example).1, 29);
+    crate::check_examples(&Day12);
+}
+
+#[test]
+fn unreachable_end() {
+    let bump = Bump::new();
+
+    assert_eq!(
+        Day12.solve_with(&bump, "SzE\n", Parts::BOTH, Params::NONE),
+        Ok((Answer::None, Answer::None))
+    );
+
+    // Only too high positions lead from the start to the end
+    let input = "abcdefghijklmnopqrstuvwxyE\nzzzzzzzzzzzzzzzzzzzzzzzzzS\n";
+
+    assert_eq!(
+        Day12.solve_with(&bump, input, Parts::BOTH, Params::NONE),
+        Ok((Answer::None, Answer::Int(25)))
+    );
 }
@@ -1,70 +1,88 @@
+use alloc::boxed::Box;
 use alloc::vec::Vec;
 use core::alloc::Allocator;
-use core::cmp::Ordering;
 use core::fmt::Debug;
 
 use nom::branch::*;
 use nom::character::complete::*;
 use nom::combinator::*;
-use nom::multi::*;
 use nom::sequence::*;
 
 use crate::nom_extra::separated_fold_many0;
+use crate::parse::{fold_all, parse_all, IResult, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
+
+const DAY: u8 = 13;
+
+pub fn day13<A: Allocator + Debug + Copy>(
+    alloc: A,
+    input: &str,
+) -> Result<Answers<'static>, ParseError> {
+    let packets = parse(alloc, input)?;
+
+    Ok((part1(&packets), part2(alloc, &packets)))
+}
+
+/// The packets, in pairs
+pub type Model<A> = Vec<Packet<A>, A>;
 
 #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day13<A: Allocator + Debug + Copy>(alloc: A, input: &str) -> (usize, usize) {
-    let (part1, _, mut packets) = fold_many0(
+pub fn parse<A: Allocator + Debug + Copy>(alloc: A, input: &str) -> Result<Model<A>, ParseError> {
+    let pairs = fold_all(
         terminated(|i| parse_pair(alloc, i), alt((eof, line_ending))),
-        || (0, 1, Vec::with_capacity_in(300, alloc)),
-        |(mut sum, ix, mut packets), (left, right)| {
-            if Packet::right_order(&left, &right).unwrap() {
-                sum += ix;
-            }
-
+        || Vec::with_capacity_in(300, alloc),
+        |mut packets, (left, right)| {
             packets.push(left);
             packets.push(right);
-
-            (sum, ix + 1, packets)
+            packets
         },
-    )(input)
-    .unwrap()
-    .1;
+    );
+
+    parse_all(DAY, input, pairs)
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<A: Allocator + Copy>(packets: &Model<A>) -> Answer<'static> {
+    packets
+        .chunks_exact(2)
+        .zip(1..)
+        .filter(|(pair, _)| Packet::right_order(&pair[0], &pair[1]).unwrap())
+        .map(|(_, ix)| ix)
+        .sum::<usize>()
+        .into()
+}
 
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2<A: Allocator + Copy>(alloc: A, packets: &Model<A>) -> Answer<'static> {
     let singleton = |x| Box::new_in([x], alloc);
 
     let a = Packet::List(singleton(Packet::List(singleton(Packet::Num(2)))));
     let b = Packet::List(singleton(Packet::List(singleton(Packet::Num(6)))));
 
-    packets.push(a.clone());
-    packets.push(b.clone());
-
-    let packet_ordering = |l: &Packet<A>, r: &Packet<A>| match Packet::right_order(l, r) {
-        None => Ordering::Equal,
-        Some(true) => Ordering::Less,
-        Some(false) => Ordering::Greater,
+    // Rather than sorting, count the packets which would come before each
+    // divider, the first one coming before the second
+    let before = |divider| {
+        packets
+            .iter()
+            .filter(|p| Packet::right_order(p, divider) == Some(true))
+            .count()
     };
 
-    packets.sort_unstable_by(&packet_ordering);
-
-    let a_find = move |x| packet_ordering(x, &a);
-    let b_find = move |x| packet_ordering(x, &b);
-
-    let a = 1 + packets.binary_search_by(a_find).unwrap();
-    let b = 1 + packets.binary_search_by(b_find).unwrap();
+    let a = 1 + before(&a);
+    let b = 2 + before(&b);
 
-    let part2 = a * b;
-
-    (part1, part2)
+    (a * b).into()
 }
 
 #[derive(Debug, Clone)]
-enum Packet<A: Allocator> {
+pub enum Packet<A: Allocator> {
     Num(u8),
     List(Box<[Packet<A>], A>),
 }
 
 impl<A: Allocator + Copy> Packet<A> {
-    fn parse(alloc: A, input: &str) -> nom::IResult<&str, Self, ()> {
+    fn parse(alloc: A, input: &str) -> IResult<&str, Self> {
         alt((
             map(u8, Packet::Num),
             preceded(
@@ -126,10 +144,7 @@ impl<A: Allocator + Copy> Packet<A> {
     }
 }
 
-fn parse_pair<A: Allocator + Copy>(
-    alloc: A,
-    input: &str,
-) -> nom::IResult<&str, (Packet<A>, Packet<A>), ()> {
+fn parse_pair<A: Allocator + Copy>(alloc: A, input: &str) -> IResult<&str, (Packet<A>, Packet<A>)> {
     terminated(
         separated_pair(
             |i| Packet::parse(alloc, i),
@@ -140,10 +155,8 @@ fn parse_pair<A: Allocator + Copy>(
     )(input)
 }
 
-#[test]
-fn both_parts() {
-    let bump = bumpalo::Bump::new();
-    let example = r#"[1,1,3,1,1]
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"[1,1,3,1,1]
 [1,1,5,1,1]
 
 [[1],[2,3,4]]
@@ -166,7 +179,51 @@ fn both_parts() {
 
 [1,[2,[3,[4,[5,6,7]]]],8,9]
 [1,[2,[3,[4,[5,6,0]]]],8,9]
-"#;
-    assert_eq!(day13(&bump, example).0, 13);
-    assert_eq!(day13(&bump, example).1, 140);
+"#,
+    params: Params::NONE,
+    part1: Some("13"),
+    part2: Some("140"),
+}];
+
+pub struct Day13;
+
+impl Solution for Day13 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "Distress Signal"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        true
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day13", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        _params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        run_phases(
+            parts,
+            phase,
+            || parse(bump, input),
+            part1,
+            |packets| part2(bump, packets),
+        )
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
+}
+
+#[test]
+fn both_parts() {
+    crate::check_examples(&Day13);
 }
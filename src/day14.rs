@@ -1,124 +1,226 @@
+use core::alloc::Allocator;
 use core::fmt::Debug;
 
+use crate::grid::Grid;
+use crate::parse::{Expected, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
+
+const DAY: u8 = 14;
+
+pub fn day14<A: Allocator + Copy>(alloc: A, input: &str) -> Result<Answers<'static>, ParseError> {
+    let cave = parse(alloc, input)?;
+
+    Ok((part1(&cave), part2(&cave)))
+}
+
+/// A slice of the cave with its rock, wide and deep enough to add the floor
+/// of part 2
+#[derive(Debug, Clone)]
+pub struct Model<A: Allocator> {
+    reservoir: Grid<Cell, A>,
+    /// Column of the source of the sand
+    source: usize,
+}
+
 #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day14(input: &str) -> (usize, usize) {
-    let mut reservoir = [[Cell::Air; 1000]; 200];
-    let mut min_x = usize::MAX;
-    let mut max_x = 0;
+pub fn parse<A: Allocator + Copy>(alloc: A, input: &str) -> Result<Model<A>, ParseError> {
+    let mut min_x = SOURCE_X;
+    let mut max_x = SOURCE_X;
     let mut max_y = 0;
 
-    for line in input.lines() {
-        let mut prev: Option<(usize, usize)> = None;
+    for point in points(input) {
+        let ((x, y), _) = point?;
 
-        for point in line.split(" -> ") {
-            let (x, y) = point.split_once(',').unwrap();
-            let x = x.parse().unwrap();
-            let y = y.parse().unwrap();
-
-            min_x = min_x.min(x);
-            max_x = max_x.max(x);
-            max_y = max_y.max(y);
-
-            match prev {
-                Some((px, py)) => {
-                    if px == x {
-                        let from = py.min(y);
-                        let to = py.max(y);
-
-                        #[allow(clippy::needless_range_loop)]
-                        for y in from..=to {
-                            reservoir[y][x] = Cell::Rock;
-                        }
-                    } else if py == y {
-                        let from = px.min(x);
-                        let to = px.max(x);
-
-                        for x in from..=to {
-                            reservoir[y][x] = Cell::Rock;
-                        }
-                    } else {
-                        panic!("Unexpected");
-                    }
-                }
-                None => {
-                    reservoir[y][x] = Cell::Rock;
+        min_x = min_x.min(x);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+
+    // The sand spreads at most one column per row, so it stays within a
+    // triangle under the source on the floor of part 2, with a column to spare
+    // on each side
+    let floor = max_y + 2;
+    let left = (min_x as isize).min(SOURCE_X as isize - floor as isize - 1);
+    let right = max_x.max(SOURCE_X + floor + 1);
+    let column = |x: usize| (x as isize - left) as usize;
+
+    let mut reservoir = Grid::new_in(column(right) + 1, floor + 1, Cell::Air, alloc);
+    let mut prev = (0, 0);
+
+    // All the points are valid by now
+    for ((x, y), joined) in points(input).flatten() {
+        let x = column(x);
+
+        if joined {
+            let (px, py) = prev;
+
+            for y in py.min(y)..=py.max(y) {
+                for x in px.min(x)..=px.max(x) {
+                    reservoir[(x, y)] = Cell::Rock;
                 }
             }
-
-            prev = Some((x, y));
+        } else {
+            reservoir[(x, y)] = Cell::Rock;
         }
+
+        prev = (x, y);
     }
 
-    let mut fallen_to_rest = 0;
+    Ok(Model {
+        reservoir,
+        source: column(SOURCE_X),
+    })
+}
 
-    while let Some((x, y)) = sand_fall_to_rest(&reservoir[..=max_y]) {
-        reservoir[y][x] = Cell::Sand;
-        fallen_to_rest += 1;
-    }
+/// The points of the paths of rock, each with whether a line of rock joins it
+/// to the previous one
+fn points(input: &str) -> impl Iterator<Item = Result<((usize, usize), bool), ParseError>> + '_ {
+    let error = |at: &str, expected| ParseError::at(DAY, input.as_bytes(), at.as_bytes(), expected);
+    let number = move |n: &str| {
+        n.parse::<usize>()
+            .map_err(|_| error(n, Expected::Token("a number")))
+    };
 
-    let part1 = fallen_to_rest;
+    input.lines().flat_map(move |line| {
+        let mut prev: Option<(usize, usize)> = None;
 
-    for cell in &mut reservoir[max_y + 2] {
-        *cell = Cell::Rock;
-    }
+        line.split(" -> ").map(move |point| {
+            let (x, y) = point
+                .split_once(',')
+                .ok_or_else(|| error(&point[point.len()..], Expected::Char(',')))?;
+            let (x, y) = (number(x)?, number(y)?);
+
+            if matches!(prev, Some((px, py)) if px != x && py != y) {
+                return Err(error(
+                    point,
+                    Expected::Token("a point in line with the previous one"),
+                ));
+            }
+
+            let joined = prev.replace((x, y)).is_some();
+
+            Ok(((x, y), joined))
+        })
+    })
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<A: Allocator + Clone>(cave: &Model<A>) -> Answer<'static> {
+    let mut reservoir = cave.reservoir.clone();
 
-    while let Some((x, y)) = sand_fall_to_rest(&reservoir[..=max_y + 2]) {
-        reservoir[y][x] = Cell::Sand;
+    fill_with_sand(&mut reservoir, cave.source).into()
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2<A: Allocator + Clone>(cave: &Model<A>) -> Answer<'static> {
+    let mut reservoir = cave.reservoir.clone();
+    let floor = reservoir.height() - 1;
+
+    reservoir.row_mut(floor).fill(Cell::Rock);
+
+    fill_with_sand(&mut reservoir, cave.source).into()
+}
+
+const SOURCE_X: usize = 500;
+
+/// Number of units of sand coming to rest before it falls out or the source
+/// is blocked
+fn fill_with_sand<A: Allocator>(reservoir: &mut Grid<Cell, A>, source: usize) -> usize {
+    let mut fallen_to_rest = 0;
+
+    while let Some(i) = sand_fall_to_rest(reservoir, source) {
+        reservoir.cells_mut()[i] = Cell::Sand;
         fallen_to_rest += 1;
     }
 
-    let part2 = fallen_to_rest;
-
-    (part1, part2)
+    fallen_to_rest
 }
 
-fn sand_fall_to_rest(reservoir: &[[Cell; 1000]]) -> Option<(usize, usize)> {
-    let mut x = 500;
-    let mut y = 0;
+/// Index among the cells of where a unit of sand comes to rest, the columns
+/// to spare keeping it from falling out on a side
+fn sand_fall_to_rest<A: Allocator>(reservoir: &Grid<Cell, A>, source: usize) -> Option<usize> {
+    let cells = reservoir.cells();
+    let mut i = source;
 
     // Stuck at start
-    if reservoir[y][x] != Cell::Air {
+    if cells[i] != Cell::Air {
         return None;
     }
 
     loop {
+        let below = i + reservoir.width();
+
         // Exits at the bottom
-        if y + 1 >= reservoir.len() {
+        if below >= cells.len() {
             return None;
         }
 
         // Try to move down
-        if reservoir[y + 1][x] == Cell::Air {
-            y += 1;
-
+        if cells[below] == Cell::Air {
+            i = below;
         // Try to move down left
-        } else if reservoir[y + 1][x - 1] == Cell::Air {
-            y += 1;
-            x -= 1;
+        } else if cells[below - 1] == Cell::Air {
+            i = below - 1;
         // Try to move down right
-        } else if reservoir[y + 1][x + 1] == Cell::Air {
-            y += 1;
-            x += 1;
-
+        } else if cells[below + 1] == Cell::Air {
+            i = below + 1;
         // Comes to a rest
         } else {
-            return Some((x, y));
+            return Some(i);
         }
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-enum Cell {
+pub enum Cell {
     Air,
     Rock,
     Sand,
 }
 
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"498,4 -> 498,6 -> 496,6
+503,4 -> 502,4 -> 502,9 -> 494,9
+"#,
+    params: Params::NONE,
+    part1: Some("24"),
+    part2: Some("93"),
+}];
+
+pub struct Day14;
+
+impl Solution for Day14 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "Regolith Reservoir"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        true
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day14", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        _params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        run_phases(parts, phase, || parse(bump, input), part1, part2)
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
+}
+
 #[test]
 fn both_parts() {
-    let example = r#"498,4 -> 498,6 -> 496,6
-503,4 -> 502,4 -> 502,9 -> 494,9
-"#;
-    assert_eq!(day14(example).0, 24);
-    assert_eq!(day14(example).1, 93);
+    crate::check_examples(&Day14);
 }
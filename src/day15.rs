@@ -5,30 +5,53 @@ use core::fmt::Debug;
 
 use crate::hash::HashSet;
 use crate::hash_set;
+use crate::interval::{Interval, IntervalSet};
 
 use nom::bytes::complete::tag;
 use nom::character::complete::*;
+use nom::combinator::all_consuming;
 use nom::sequence::*;
 
-#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day15<A: Allocator + Debug + Copy>(alloc: A, input: &str) -> (usize, usize) {
-    let (sensors, beacons) = parse(alloc, input);
+use crate::parse::{Error, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Param, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
 
-    let part1 = positions_not_present(alloc, 2000000, &sensors, &beacons);
-    let beacon = find_isolated(alloc, 4000000, 4000000, &sensors).unwrap();
+const DAY: u8 = 15;
 
-    let part2 = beacon.x as usize * 4000000 + beacon.y as usize;
+pub fn day15<A: Allocator + Debug + Copy>(
+    alloc: A,
+    input: &str,
+) -> Result<Answers<'static>, ParseError> {
+    let sensors = parse(alloc, input)?;
+    let config = Config::default();
+
+    Ok((
+        part1(alloc, &sensors, &config),
+        part2(alloc, &sensors, &config),
+    ))
+}
 
-    (part1, part2)
+// On the scale of the puzzle's coordinates, millions at most, so that part 2
+// doesn't search billions of rows and the tuning frequency fits in a usize
+crate::day_config! {
+    /// Row on which to count the positions where a beacon can't be
+    row: i32 = 2000000, in -16_000_000..=16_000_000;
+    /// Largest coordinate the distress beacon can have, whose x coordinate
+    /// is multiplied by 4000000 in its tuning frequency
+    bound: i32 = 4000000, in 0..=4_000_000;
 }
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-struct Pos {
+pub struct Pos {
     x: i32,
     y: i32,
 }
 
-fn parse<A: Allocator + Copy>(alloc: A, input: &str) -> (Vec<(Pos, u32), A>, HashSet<Pos, A>) {
+/// Sensors with the distance to their closest beacon, and the beacons
+pub type Model<A> = (Vec<(Pos, u32), A>, HashSet<Pos, A>);
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn parse<A: Allocator + Copy>(alloc: A, input: &str) -> Result<Model<A>, ParseError> {
     let mut sensors = Vec::with_capacity_in(16, alloc);
     let mut beacons = hash_set!(16, alloc);
 
@@ -37,8 +60,8 @@ fn parse<A: Allocator + Copy>(alloc: A, input: &str) -> (Vec<(Pos, u32), A>, Has
             continue;
         }
 
-        let (_, sx, _, sy, _, bx, _, by) = tuple((
-            tag::<_, _, ()>("Sensor at x="),
+        let (_, sx, _, sy, _, bx, _, by) = all_consuming(tuple((
+            tag::<_, _, Error<_>>("Sensor at x="),
             i32,
             tag(", y="),
             i32,
@@ -46,8 +69,8 @@ fn parse<A: Allocator + Copy>(alloc: A, input: &str) -> (Vec<(Pos, u32), A>, Has
             i32,
             tag(", y="),
             i32,
-        ))(line)
-        .unwrap()
+        )))(line)
+        .map_err(|err| ParseError::from_nom(DAY, input.as_bytes(), err))?
         .1;
 
         let manhattan = sx.abs_diff(bx) + sy.abs_diff(by);
@@ -56,10 +79,31 @@ fn parse<A: Allocator + Copy>(alloc: A, input: &str) -> (Vec<(Pos, u32), A>, Has
         beacons.insert(Pos { x: bx, y: by });
     }
 
-    (sensors, beacons)
+    Ok((sensors, beacons))
 }
 
-fn sensor_no_beacon_interval_on(on_y: i32, sensor: (Pos, u32)) -> Option<Interval> {
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<A: Allocator + Debug + Copy>(
+    alloc: A,
+    (sensors, beacons): &Model<A>,
+    config: &Config,
+) -> Answer<'static> {
+    positions_not_present(alloc, config.row, sensors, beacons).into()
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2<A: Allocator + Debug + Copy>(
+    alloc: A,
+    (sensors, _): &Model<A>,
+    config: &Config,
+) -> Answer<'static> {
+    // No answer when the parameters don't match the input
+    find_isolated(alloc, config.bound, config.bound, sensors).map_or(Answer::None, |beacon| {
+        (beacon.x as usize * 4000000 + beacon.y as usize).into()
+    })
+}
+
+fn sensor_no_beacon_interval_on(on_y: i32, sensor: (Pos, u32)) -> Option<Interval<i32>> {
     let y_dist = sensor.0.y.abs_diff(on_y);
 
     match sensor.1.cmp(&y_dist) {
@@ -111,229 +155,83 @@ fn find_isolated<A: Allocator + Debug + Copy>(
 
         interval_set.limit_by(Interval::new(0, up_to_x));
 
-        if interval_set.intervals.len() > 1 {
-            assert_eq!(interval_set.intervals.len(), 2);
-            let x = interval_set.intervals[0].b + 1;
-
-            return Some(Pos { x, y });
+        if let Some(gap) = interval_set.gaps().next() {
+            return Some(Pos { x: gap.start(), y });
         }
 
-        interval_set.intervals.clear();
+        interval_set.clear();
     }
 
     None
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-struct Interval {
-    // invariant: a <= b
-    a: i32,
-    b: i32,
-}
-
-impl Interval {
-    fn new(a: i32, b: i32) -> Self {
-        Self {
-            a: a.min(b),
-            b: a.max(b),
-        }
-    }
-
-    fn join(&self, other: &Self) -> Option<Self> {
-        let combined = Self {
-            a: self.a.min(other.a),
-            b: self.b.max(other.b),
-        };
-
-        match self.a.cmp(&other.a) {
-            Ordering::Less if self.b < other.a => None,
-            Ordering::Greater if self.a > other.b => None,
-            _ => Some(combined),
-        }
-    }
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"
+Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+Sensor at x=9, y=16: closest beacon is at x=10, y=16
+Sensor at x=13, y=2: closest beacon is at x=15, y=3
+Sensor at x=12, y=14: closest beacon is at x=10, y=16
+Sensor at x=10, y=20: closest beacon is at x=10, y=16
+Sensor at x=14, y=17: closest beacon is at x=10, y=16
+Sensor at x=8, y=7: closest beacon is at x=2, y=10
+Sensor at x=2, y=0: closest beacon is at x=2, y=10
+Sensor at x=0, y=11: closest beacon is at x=2, y=10
+Sensor at x=20, y=14: closest beacon is at x=25, y=17
+Sensor at x=17, y=20: closest beacon is at x=21, y=22
+Sensor at x=16, y=7: closest beacon is at x=15, y=3
+Sensor at x=14, y=3: closest beacon is at x=15, y=3
+Sensor at x=20, y=1: closest beacon is at x=15, y=3
+"#,
+    params: Params(&[("row", 10), ("bound", 20)]),
+    part1: Some("26"),
+    part2: Some("56000011"),
+}];
 
-    fn count(&self) -> usize {
-        (self.b - self.a + 1) as usize
-    }
+pub struct Day15;
 
-    fn contains(&self, x: &i32) -> bool {
-        (self.a..=self.b).contains(x)
+impl Solution for Day15 {
+    fn day(&self) -> u8 {
+        DAY
     }
-}
 
-#[derive(Debug)]
-struct IntervalSet<A: Allocator> {
-    // invariant: ordered, non-overlapping
-    intervals: Vec<Interval, A>,
-}
-
-impl<A: Allocator> IntervalSet<A> {
-    fn new_in(alloc: A) -> IntervalSet<A> {
-        IntervalSet {
-            intervals: Vec::new_in(alloc),
-        }
+    fn title(&self) -> &'static str {
+        "Beacon Exclusion Zone"
     }
 
-    fn limit_by(&mut self, limit: Interval) {
-        let mut start_remove_count = 0;
-        let mut end_remove_count = 0;
-
-        for i in 0..self.intervals.len() {
-            if limit.a > self.intervals[i].a {
-                if limit.a > self.intervals[i].b {
-                    start_remove_count += 1;
-                } else {
-                    self.intervals[i].a = limit.a;
-                }
-            }
-
-            if limit.b < self.intervals[i].b {
-                if limit.b < self.intervals[i].a {
-                    end_remove_count += 1;
-                } else {
-                    self.intervals[i].b = limit.b;
-                }
-            }
-        }
-
-        self.intervals.splice(0..start_remove_count, []);
-        self.intervals
-            .splice((self.intervals.len() - end_remove_count).., []);
+    fn needs_allocator(&self) -> bool {
+        true
     }
 
-    fn insert(&mut self, mut interval: Interval) {
-        let start_ix = self
-            .intervals
-            .binary_search_by(|x| x.a.cmp(&interval.a))
-            .unwrap_or_else(|x| x);
-
-        let mut low_ix = None;
-        let mut high_ix = None;
-
-        let ix = start_ix;
-
-        if let Some(new_interval) = self.intervals.get(ix).and_then(|x| interval.join(x)) {
-            interval = new_interval;
-            low_ix = Some(ix);
-        }
-
-        if let Some(mut ix) = start_ix.checked_sub(1) {
-            while let Some(new_interval) = self.intervals.get(ix).and_then(|x| interval.join(x)) {
-                interval = new_interval;
-                low_ix = Some(ix);
-
-                if ix == 0 {
-                    break;
-                }
-
-                ix -= 1;
-            }
-        }
-
-        let mut ix = start_ix;
-
-        while let Some(new_interval) = self.intervals.get(ix).and_then(|x| interval.join(x)) {
-            interval = new_interval;
-            high_ix = Some(ix);
-            ix += 1;
-        }
-
-        match (low_ix, high_ix) {
-            (Some(low_ix), Some(high_ix)) => {
-                let new_interval = [interval];
-                self.intervals.splice(low_ix..=high_ix, new_interval);
-            }
-            (Some(low_ix), None) => {
-                self.intervals[low_ix] = interval;
-            }
-            (None, Some(high_ix)) => {
-                self.intervals[high_ix] = interval;
-            }
-            (None, None) => self.intervals.insert(start_ix, interval),
-        }
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day15", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        let config = Config::from_params(DAY, params)?;
+
+        run_phases(
+            parts,
+            phase,
+            || parse(bump, input),
+            |sensors| part1(bump, sensors, &config),
+            |sensors| part2(bump, sensors, &config),
+        )
     }
 
-    fn count(&self) -> usize {
-        self.intervals.iter().map(|i| i.count()).sum()
+    fn params(&self) -> &'static [Param] {
+        Config::PARAMS
     }
 
-    fn contains(&self, x: &i32) -> bool {
-        self.intervals.iter().any(|i| i.contains(x))
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
     }
 }
 
-#[test]
-fn intervals() {
-    let bump = bumpalo::Bump::new();
-    let mut set = IntervalSet::new_in(&bump);
-
-    set.insert(Interval::new(1, 3));
-    assert_eq!(&set.intervals[..], &[Interval::new(1, 3)]);
-
-    set.insert(Interval::new(4, 4));
-    assert_eq!(
-        &set.intervals[..],
-        &[Interval::new(1, 3), Interval::new(4, 4)]
-    );
-
-    set.insert(Interval::new(-3, 0));
-    assert_eq!(
-        &set.intervals[..],
-        &[
-            Interval::new(-3, 0),
-            Interval::new(1, 3),
-            Interval::new(4, 4)
-        ]
-    );
-
-    set.insert(Interval::new(1, 2));
-    assert_eq!(
-        &set.intervals[..],
-        &[
-            Interval::new(-3, 0),
-            Interval::new(1, 3),
-            Interval::new(4, 4)
-        ]
-    );
-
-    set.insert(Interval::new(-1, 2));
-    assert_eq!(
-        &set.intervals[..],
-        &[Interval::new(-3, 3), Interval::new(4, 4)]
-    );
-
-    set.insert(Interval::new(-3, 10));
-    assert_eq!(&set.intervals[..], &[Interval::new(-3, 10)]);
-
-    set.insert(Interval::new(10, 15));
-    assert_eq!(&set.intervals[..], &[Interval::new(-3, 15)]);
-}
-
 #[test]
 fn both_parts() {
-    let bump = bumpalo::Bump::new();
-    let example = r#"
-Sensor at x=2, y=18: closest beacon is at x=-2, y=15
-Sensor at x=9, y=16: closest beacon is at x=10, y=16
-Sensor at x=13, y=2: closest beacon is at x=15, y=3
-Sensor at x=12, y=14: closest beacon is at x=10, y=16
-Sensor at x=10, y=20: closest beacon is at x=10, y=16
-Sensor at x=14, y=17: closest beacon is at x=10, y=16
-Sensor at x=8, y=7: closest beacon is at x=2, y=10
-Sensor at x=2, y=0: closest beacon is at x=2, y=10
-Sensor at x=0, y=11: closest beacon is at x=2, y=10
-Sensor at x=20, y=14: closest beacon is at x=25, y=17
-Sensor at x=17, y=20: closest beacon is at x=21, y=22
-Sensor at x=16, y=7: closest beacon is at x=15, y=3
-Sensor at x=14, y=3: closest beacon is at x=15, y=3
-Sensor at x=20, y=1: closest beacon is at x=15, y=3
-"#;
-
-    let (sensors, beacons) = parse(&bump, example);
-
-    assert_eq!(positions_not_present(&bump, 10, &sensors, &beacons), 26);
-    assert_eq!(
-        find_isolated(&bump, 20, 20, &sensors),
-        Some(Pos { x: 14, y: 11 })
-    );
+    crate::check_examples(&Day15);
 }
@@ -6,16 +6,37 @@ use crate::hash::HashSet;
 use crate::hash_set;
 use nom::character::complete::*;
 use nom::combinator::*;
-use nom::multi::*;
-use nom::sequence::*;
+
+use crate::nom_extra::{fold_lines, number_array};
+use crate::parse::{parse_all, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
+
+const DAY: u8 = 18;
+
+pub fn day18<A: Allocator + Debug + Copy>(
+    alloc: A,
+    input: &str,
+) -> Result<Answers<'static>, ParseError> {
+    let droplet = parse(alloc, input)?;
+
+    Ok((part1(&droplet), part2(alloc, &droplet)))
+}
+
+/// Smallest and largest coordinate of the cubes on each axis
+type Bounds = (u8, u8, u8, u8, u8, u8);
+
+/// The cubes of lava, and their bounds
+#[derive(Debug)]
+pub struct Model<A: Allocator + Clone> {
+    cubes: HashSet<(u8, u8, u8), A>,
+    bounds: Bounds,
+}
 
 #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day18<A: Allocator + Debug + Copy>(alloc: A, input: &str) -> (usize, usize) {
-    let (bounds @ (a_min, a_max, b_min, b_max, c_min, c_max), cubes) = fold_many0(
-        map(
-            tuple((u8::<&str, ()>, char(','), u8, char(','), u8, newline)),
-            |(a, _, b, _, c, _)| (a, b, c),
-        ),
+pub fn parse<A: Allocator + Debug + Copy>(alloc: A, input: &str) -> Result<Model<A>, ParseError> {
+    let cubes = fold_lines(
+        map(number_array(char(',')), |[a, b, c]: [u8; 3]| (a, b, c)),
         || {
             (
                 (u8::MAX, u8::MIN, u8::MAX, u8::MIN, u8::MAX, u8::MIN),
@@ -36,9 +57,32 @@ pub fn day18<A: Allocator + Debug + Copy>(alloc: A, input: &str) -> (usize, usiz
                 hm,
             )
         },
-    )(input)
-    .unwrap()
-    .1;
+    );
+
+    let (bounds, cubes) = parse_all(DAY, input, cubes)?;
+
+    Ok(Model { cubes, bounds })
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<A: Allocator + Clone>(droplet: &Model<A>) -> Answer<'static> {
+    let cubes = &droplet.cubes;
+
+    cubes
+        .iter()
+        .flat_map(neighbours)
+        .filter(|neighbour| match neighbour {
+            Some(neighbour) => !cubes.contains(neighbour),
+            None => true,
+        })
+        .count()
+        .into()
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2<A: Allocator + Copy>(alloc: A, droplet: &Model<A>) -> Answer<'static> {
+    let Model { cubes, bounds } = droplet;
+    let (a_min, a_max, b_min, b_max, c_min, c_max) = *bounds;
 
     let as_ = a_min..=a_max;
     let bs = b_min..=b_max;
@@ -69,7 +113,7 @@ pub fn day18<A: Allocator + Debug + Copy>(alloc: A, input: &str) -> (usize, usiz
             }
 
             // Outside the bounds
-            if outside_bounds(neighbour, &bounds) {
+            if outside_bounds(neighbour, bounds) {
                 return false;
             }
 
@@ -77,27 +121,19 @@ pub fn day18<A: Allocator + Debug + Copy>(alloc: A, input: &str) -> (usize, usiz
         }));
     }
 
-    cubes.iter().flat_map(neighbours).fold(
-        (0, 0),
-        |(mut open_faces, mut face_reachable), neighbour| {
-            if let Some(neighbour) = neighbour {
-                if !cubes.contains(&neighbour) {
-                    open_faces += 1;
-
-                    if reachable_from_outside.contains(&neighbour)
-                        || outside_bounds(&neighbour, &bounds)
-                    {
-                        face_reachable += 1;
-                    }
-                }
-            } else {
-                open_faces += 1;
-                face_reachable += 1;
+    cubes
+        .iter()
+        .flat_map(neighbours)
+        .filter(|neighbour| match neighbour {
+            Some(neighbour) => {
+                !cubes.contains(neighbour)
+                    && (reachable_from_outside.contains(neighbour)
+                        || outside_bounds(neighbour, bounds))
             }
-
-            (open_faces, face_reachable)
-        },
-    )
+            None => true,
+        })
+        .count()
+        .into()
 }
 
 fn gen_points<'a>(
@@ -111,7 +147,7 @@ fn gen_points<'a>(
 
 fn outside_bounds(
     (a, b, c): &(u8, u8, u8),
-    (a_min, a_max, b_min, b_max, c_min, c_max): &(u8, u8, u8, u8, u8, u8),
+    (a_min, a_max, b_min, b_max, c_min, c_max): &Bounds,
 ) -> bool {
     a < a_min || a > a_max || b < b_min || b > b_max || c < c_min || c > c_max
 }
@@ -135,10 +171,8 @@ fn neighbours(&(a, b, c): &(u8, u8, u8)) -> impl Iterator<Item = Option<(u8, u8,
     .into_iter()
 }
 
-#[test]
-fn both_parts() {
-    let bump = bumpalo::Bump::new();
-    let example = r#"2,2,2
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"2,2,2
 1,2,2
 3,2,2
 2,1,2
@@ -151,7 +185,51 @@ fn both_parts() {
 3,2,5
 2,1,5
 2,3,5
-"#;
-    assert_eq!(day18(&bump, example).0, 64);
-    assert_eq!(day18(&bump, example).1, 58);
+"#,
+    params: Params::NONE,
+    part1: Some("64"),
+    part2: Some("58"),
+}];
+
+pub struct Day18;
+
+impl Solution for Day18 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "Boiling Boulders"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        true
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day18", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        _params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        run_phases(
+            parts,
+            phase,
+            || parse(bump, input),
+            part1,
+            |droplet| part2(bump, droplet),
+        )
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
+}
+
+#[test]
+fn both_parts() {
+    crate::check_examples(&Day18);
 }
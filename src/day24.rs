@@ -1,152 +1,244 @@
+use alloc::vec::Vec;
 use core::alloc::Allocator;
+use core::cell::Cell;
 use core::fmt::Debug;
 use core::iter::once;
 
-use heapless::binary_heap::Min;
-use heapless::BinaryHeap;
+use crate::bitset::DynBitSet;
+use crate::grid::Grid;
+use crate::parse::{byte, parse_all, Expected, ParseError};
+use crate::pathfinding::{a_star, Graph, MinHeap, NoPath, Visited};
+use crate::{run_phases, Answer, Answers, Example, Params, Parts, Phase, Solution};
+use bumpalo::Bump;
 
-use crate::bitset::{U128Set, U32Set};
-use crate::hash::HashSet;
-use crate::hash_set;
+const DAY: u8 = 24;
 
-#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day24<A: Allocator + Debug + Copy>(alloc: A, input: &str) -> (u16, u16) {
-    let bassin = Bassin::parse(alloc, input);
-
-    let start_x = 0;
-    let start_y = 0;
-    let end_x = bassin.width - 1;
-    let end_y = bassin.height - 1;
-
-    let part1 = a_star(
-        alloc,
-        &bassin,
-        Pos {
-            time: 0,
-            x: start_x,
-            y: start_y,
-        },
-        end_x,
-        end_y,
-    );
+pub fn day24<A: Allocator + Debug + Copy>(
+    alloc: A,
+    input: &str,
+) -> Result<Answers<'static>, ParseError> {
+    let valley = parse(alloc, input)?;
 
-    let back = a_star(
-        alloc,
-        &bassin,
-        Pos {
-            time: part1,
-            x: end_x,
-            y: end_y,
-        },
-        start_x,
-        start_y,
-    );
+    Ok((part1(alloc, &valley), part2(alloc, &valley)))
+}
 
-    let part2 = a_star(
-        alloc,
-        &bassin,
-        Pos {
-            time: back,
-            x: start_x,
-            y: start_y,
-        },
-        end_x,
-        end_y,
-    );
+/// The basin's blizzards, and the time of the first crossing once searched,
+/// if there is a way through
+///
+/// Part 2 starts from where part 1 ends, so the first crossing is only
+/// searched once when solving both parts.
+#[derive(Debug)]
+pub struct Model<A: Allocator> {
+    bassin: Bassin<A>,
+    first_crossing: Cell<Option<Option<u32>>>,
+}
+
+impl<A: Allocator + Copy> Model<A> {
+    fn start(&self) -> (u16, u16) {
+        (0, 0)
+    }
+
+    fn end(&self) -> (u16, u16) {
+        (self.bassin.width - 1, self.bassin.height - 1)
+    }
 
-    (part1, part2)
+    /// Time at which the end is first reached, from the start at time 0
+    fn first_crossing(&self, alloc: A) -> Option<u32> {
+        if let Some(time) = self.first_crossing.get() {
+            return time;
+        }
+
+        let time = self.cross(alloc, 0, self.start(), self.end());
+        self.first_crossing.set(Some(time));
+
+        time
+    }
+
+    /// Time at which `goal` is first reached, from `(x, y)` at `time`, if
+    /// there is a way through
+    fn cross(&self, alloc: A, time: u32, (x, y): (u16, u16), goal: (u16, u16)) -> Option<u32> {
+        let (width, height) = (self.bassin.width as usize, self.bassin.height as usize);
+
+        // The blizzards are back where they started after a whole period,
+        // give up once they went through one and every position could have
+        // been walked through
+        let period = num::integer::lcm(width, height - 2);
+        let deadline = (time as usize + period + width * height).min(u32::MAX as usize) as u32;
+
+        let crossing = Crossing {
+            bassin: &self.bassin,
+            goal,
+            deadline,
+        };
+
+        // The time plus the estimate of the positions searched grows by at
+        // most 2 each minute, so the times of the positions still to search
+        // are no further apart than the longest estimate and a few minutes
+        let window = width + height + 2;
+
+        let (_, end) = a_star(
+            &crossing,
+            Pos { time, x, y },
+            |pos| (pos.x, pos.y) == goal,
+            &mut RecentlySeen::new(alloc, window, (self.bassin.width, self.bassin.height)),
+            &mut MinHeap::with_capacity_in(2048, alloc),
+            &mut NoPath,
+        )?;
+
+        Some(end.time)
+    }
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn parse<A: Allocator + Copy>(alloc: A, input: &str) -> Result<Model<A>, ParseError> {
+    Ok(Model {
+        bassin: Bassin::parse(alloc, input)?,
+        first_crossing: Cell::new(None),
+    })
 }
 
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<A: Allocator + Copy>(alloc: A, valley: &Model<A>) -> Answer<'static> {
+    valley
+        .first_crossing(alloc)
+        .map_or(Answer::None, Answer::from)
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part2<A: Allocator + Copy>(alloc: A, valley: &Model<A>) -> Answer<'static> {
+    valley
+        .first_crossing(alloc)
+        .and_then(|there| valley.cross(alloc, there, valley.end(), valley.start()))
+        .and_then(|back| valley.cross(alloc, back, valley.start(), valley.end()))
+        .map_or(Answer::None, Answer::from)
+}
+
+/// The blizzards at time 0 going each way, by `y * width + x`
 #[derive(Debug)]
 struct Bassin<A: Allocator> {
-    columns_up: Vec<U32Set, A>,
-    columns_down: Vec<U32Set, A>,
-    rows_left: Vec<U128Set, A>,
-    rows_right: Vec<U128Set, A>,
-    width: u8,
-    height: u8,
+    up: DynBitSet<A>,
+    down: DynBitSet<A>,
+    left: DynBitSet<A>,
+    right: DynBitSet<A>,
+    width: u16,
+    height: u16,
 }
 
 impl<A: Allocator + Copy> Bassin<A> {
-    fn parse(alloc: A, input: &str) -> Self {
-        let mut columns_up = Vec::with_capacity_in(121, alloc);
-        let mut columns_down = Vec::with_capacity_in(121, alloc);
-        let mut rows_left = Vec::with_capacity_in(25, alloc);
-        let mut rows_right = Vec::with_capacity_in(25, alloc);
-        let mut width = 0;
-        let mut height = 0;
-
-        for (y, line) in input.lines().enumerate() {
-            rows_left.push(U128Set::empty());
-            rows_right.push(U128Set::empty());
-
-            let line = line.as_bytes();
-
-            let line = line
-                .strip_prefix(&[b'#'])
-                .unwrap()
-                .strip_suffix(&[b'#'])
-                .unwrap();
-
-            for (x, c) in line.iter().enumerate() {
-                if columns_up.get(x).is_none() {
-                    columns_up.push(U32Set::empty());
-                }
-                if columns_down.get(x).is_none() {
-                    columns_down.push(U32Set::empty());
-                }
+    fn parse(alloc: A, input: &str) -> Result<Self, ParseError> {
+        let error = |at, expected| ParseError::at(DAY, input.as_bytes(), at, expected);
+        let line = |y| input.lines().nth(y).unwrap_or_default().as_bytes();
+
+        let tile = byte("a blizzard, '.' or '#'", |c| {
+            matches!(c, b'>' | b'<' | b'v' | b'^' | b'.' | b'#').then_some(c)
+        });
+        let tiles = parse_all(DAY, input.as_bytes(), Grid::parser(alloc, tile))?;
+
+        for (y, row) in tiles.rows().enumerate() {
+            if row.first() != Some(&b'#') {
+                return Err(error(line(y), Expected::Char('#')));
+            }
+            if row.len() < 2 || row.last() != Some(&b'#') {
+                let line = line(y);
+                return Err(error(&line[line.len()..], Expected::Char('#')));
+            }
+        }
 
-                match c {
-                    b'>' => rows_right[y].insert(x as u8),
-                    b'<' => rows_left[y].insert(x as u8),
-                    b'v' => columns_down[x].insert(y as u8),
-                    b'^' => columns_up[x].insert(y as u8),
-                    b'.' => {}
-                    b'#' => {}
-                    _ => panic!("Unexpected"),
+        // Without the walls on each side
+        let width = tiles.width().saturating_sub(2);
+        let height = tiles.height();
+
+        if height < 3 {
+            let eof = &input.as_bytes()[input.len()..];
+            return Err(error(
+                eof,
+                Expected::Token("a row between the top and bottom walls"),
+            ));
+        }
+        if width == 0 {
+            return Err(error(&line(0)[1..], Expected::Char('.')));
+        }
+
+        // The start in the top wall, the end in the bottom one
+        for (y, opening) in [(0, 0), (height - 1, width - 1)] {
+            for (x, &c) in tiles.row(y)[1..=width].iter().enumerate() {
+                let wall = if x == opening { b'.' } else { b'#' };
+
+                if c != wall {
+                    return Err(error(&line(y)[x + 1..], Expected::Char(wall as char)));
                 }
             }
+        }
+
+        // Positions are stored on 16 bits
+        let max = u16::MAX as usize;
 
-            width = line.len() as u8;
-            height = (y + 1) as u8;
+        if width > max || height > max {
+            return Err(error(
+                line(if width > max { 0 } else { max }),
+                Expected::Token("a basin of at most 65535 columns and 65535 rows"),
+            ));
         }
 
-        Bassin {
-            columns_up,
-            columns_down,
-            rows_left,
-            rows_right,
-            width,
-            height,
+        let blizzards = || DynBitSet::with_capacity_in(width * height, alloc);
+        let (mut up, mut down, mut left, mut right) =
+            (blizzards(), blizzards(), blizzards(), blizzards());
+
+        for (y, row) in tiles.rows().enumerate() {
+            for (x, &c) in row[1..=width].iter().enumerate() {
+                let i = y * width + x;
+
+                match c {
+                    b'^' => up.insert(i),
+                    b'v' => down.insert(i),
+                    b'<' => left.insert(i),
+                    b'>' => right.insert(i),
+                    _ => {}
+                }
+            }
         }
+
+        Ok(Bassin {
+            up,
+            down,
+            left,
+            right,
+            width: width as u16,
+            height: height as u16,
+        })
+    }
+
+    /// Whether one of `blizzards` is at `(x, y)` at time 0
+    fn blizzard_start(&self, blizzards: &DynBitSet<A>, x: isize, y: isize) -> bool {
+        blizzards.contains(y as usize * self.width as usize + x as usize)
     }
 
     fn up_blizzard_at(&self, pos: Pos) -> bool {
         let wrap = self.height as isize - 2;
         let blizzard_y = (pos.y as isize - 1 + pos.time as isize).rem_euclid(wrap) + 1;
 
-        self.columns_up[pos.x as usize].contains(blizzard_y as u8)
+        self.blizzard_start(&self.up, pos.x as isize, blizzard_y)
     }
 
     fn down_blizzard_at(&self, pos: Pos) -> bool {
         let wrap = self.height as isize - 2;
         let blizzard_y = (pos.y as isize - 1 - pos.time as isize).rem_euclid(wrap) + 1;
 
-        self.columns_down[pos.x as usize].contains(blizzard_y as u8)
+        self.blizzard_start(&self.down, pos.x as isize, blizzard_y)
     }
 
     fn right_blizzard_at(&self, pos: Pos) -> bool {
         let wrap = self.width as isize;
         let blizzard_x = (pos.x as isize - pos.time as isize).rem_euclid(wrap);
 
-        self.rows_right[pos.y as usize].contains(blizzard_x as u8)
+        self.blizzard_start(&self.right, blizzard_x, pos.y as isize)
     }
 
     fn left_blizzard_at(&self, pos: Pos) -> bool {
         let wrap = self.width as isize;
         let blizzard_x = (pos.x as isize + pos.time as isize).rem_euclid(wrap);
 
-        self.rows_left[pos.y as usize].contains(blizzard_x as u8)
+        self.blizzard_start(&self.left, blizzard_x, pos.y as isize)
     }
 
     fn blizzard_at(&self, pos: Pos) -> bool {
@@ -168,7 +260,8 @@ impl<A: Allocator + Copy> Bassin<A> {
     }
 
     #[allow(dead_code)]
-    fn draw(&self, time: u16, pos_x: Option<u8>, pos_y: Option<u8>) {
+    #[cfg(feature = "std")]
+    fn draw(&self, time: u32, pos_x: Option<u16>, pos_y: Option<u16>) {
         for y in 0..self.height {
             print!("#");
             if y == 0 || y == self.height - 1 {
@@ -219,13 +312,13 @@ impl<A: Allocator + Copy> Bassin<A> {
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 struct Pos {
-    time: u16,
-    x: u8,
-    y: u8,
+    time: u32,
+    x: u16,
+    y: u16,
 }
 
 impl Pos {
-    fn next(self, width: u8, height: u8) -> impl Iterator<Item = Self> {
+    fn next(self, width: u16, height: u16) -> impl Iterator<Item = Self> {
         let same_pos = Pos {
             time: self.time + 1,
             ..self
@@ -260,88 +353,195 @@ impl Pos {
 }
 
 // Way to not store all seen, but only the recent ones
-struct RecentlySeen<const NUM_BUCKETS: usize, A: Allocator + Clone> {
-    buckets: [(Option<u16>, HashSet<Pos, A>); NUM_BUCKETS],
+struct RecentlySeen<A: Allocator> {
+    /// The positions seen at a time, by `y * width + x`, for the times
+    /// falling on each bucket
+    buckets: Vec<(Option<u32>, DynBitSet<A>), A>,
+    width: usize,
 }
 
-impl<const NUM_BUCKETS: usize, A: Allocator + Copy> RecentlySeen<NUM_BUCKETS, A> {
-    fn new(alloc: A, bucket_capacity: usize) -> Self {
-        let mut buckets = Vec::with_capacity_in(NUM_BUCKETS, alloc);
+impl<A: Allocator + Copy> RecentlySeen<A> {
+    fn new(alloc: A, num_buckets: usize, (width, height): (u16, u16)) -> Self {
+        let (width, height) = (width as usize, height as usize);
+        let mut buckets = Vec::with_capacity_in(num_buckets, alloc);
 
-        for _ in 0..NUM_BUCKETS {
-            buckets.push((None, hash_set!(bucket_capacity, alloc)));
+        for _ in 0..num_buckets {
+            buckets.push((None, DynBitSet::with_capacity_in(width * height, alloc)));
         }
 
-        RecentlySeen {
-            buckets: buckets.try_into().unwrap(),
-        }
+        RecentlySeen { buckets, width }
     }
 
     fn insert(&mut self, pos: Pos) -> bool {
-        let (k, s) = &mut self.buckets[pos.time as usize % NUM_BUCKETS];
+        let num_buckets = self.buckets.len();
+        let (k, s) = &mut self.buckets[pos.time as usize % num_buckets];
+        let i = pos.y as usize * self.width + pos.x as usize;
 
-        if *k == Some(pos.time) {
-            s.insert(pos)
-        } else {
+        if k.replace(pos.time) != Some(pos.time) {
             // Recycle the old one
-            k.replace(pos.time);
             s.clear();
-            s.insert(pos)
         }
-    }
 
-    fn contains(&self, pos: &Pos) -> bool {
-        let (k, s) = &self.buckets[pos.time as usize % NUM_BUCKETS];
-        *k == Some(pos.time) && s.contains(pos)
+        if s.contains(i) {
+            false
+        } else {
+            s.insert(i);
+            true
+        }
     }
 }
 
-fn a_star<A: Allocator + Copy>(
-    alloc: A,
-    bassin: &Bassin<A>,
-    start: Pos,
-    goal_x: u8,
-    goal_y: u8,
-) -> u16 {
-    macro_rules! heuristic {
-        ($pos:expr) => {{
-            let manhattan = $pos.x.abs_diff(goal_x) + $pos.y.abs_diff(goal_y);
-            $pos.time + manhattan as u16
-        }};
+/// Positions already reached are reached again no sooner, the time being part
+/// of them
+impl<A: Allocator + Copy> Visited<Pos, u32> for RecentlySeen<A> {
+    fn improve(&mut self, pos: Pos, _cost: u32) -> bool {
+        self.insert(pos)
     }
+}
 
-    let mut to_visit = BinaryHeap::<_, Min, 2048>::new();
-    to_visit.push((heuristic!(start), start)).unwrap();
+/// The moves through the basin towards `goal`, one each minute until
+/// `deadline`
+struct Crossing<'a, A: Allocator> {
+    bassin: &'a Bassin<A>,
+    goal: (u16, u16),
+    deadline: u32,
+}
 
-    let mut seen = RecentlySeen::<32, _>::new(alloc, 64);
-    seen.insert(start);
+impl<A: Allocator + Copy> Graph for Crossing<'_, A> {
+    type Node = Pos;
+    type Cost = u32;
 
-    while let Some((_, current)) = to_visit.pop() {
-        if current.x == goal_x && current.y == goal_y {
-            return current.time;
+    fn successors(&self, pos: Pos, mut visit: impl FnMut(Pos, u32)) {
+        if pos.time >= self.deadline {
+            return;
         }
 
-        for next in current.next(bassin.width, bassin.height) {
-            if !seen.contains(&next) && bassin.can_move_to(next) {
-                to_visit.push((heuristic!(next), next)).unwrap();
-                seen.insert(next);
+        for next in pos.next(self.bassin.width, self.bassin.height) {
+            if self.bassin.can_move_to(next) {
+                visit(next, 1);
             }
         }
     }
 
-    panic!("Solution not found");
+    fn heuristic(&self, pos: Pos) -> u32 {
+        let (goal_x, goal_y) = self.goal;
+
+        u32::from(pos.x.abs_diff(goal_x)) + u32::from(pos.y.abs_diff(goal_y))
+    }
 }
 
-#[test]
-fn both_parts() {
-    let bump = bumpalo::Bump::new();
-    let example = r#"#.######
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"#.######
 #>>.<^<#
 #.<..<<#
 #>v.><>#
 #<^v^^>#
 ######.#
-"#;
-    assert_eq!(day24(&bump, example).0, 18);
-    assert_eq!(day24(&bump, example).1, 54);
+"#,
+    params: Params::NONE,
+    part1: Some("18"),
+    part2: Some("54"),
+}];
+
+pub struct Day24;
+
+impl Solution for Day24 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "Blizzard Basin"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        true
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day24", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        _params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        run_phases(
+            parts,
+            phase,
+            || parse(bump, input),
+            |valley| part1(bump, valley),
+            |valley| part2(bump, valley),
+        )
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
+}
+
+#[test]
+fn both_parts() {
+    crate::check_examples(&Day24);
+}
+
+#[test]
+fn larger_than_bitsets() {
+    use alloc::string::String;
+
+    let bump = Bump::new();
+    let (width, height) = (300, 140);
+    let mut input = String::new();
+
+    // An empty basin wider and taller than a `u128` or `u32` of blizzards,
+    // and wider than a byte
+    input.push_str("#.");
+    input.extend(core::iter::repeat('#').take(width));
+    input.push('\n');
+
+    for _ in 0..height {
+        input.push('#');
+        input.extend(core::iter::repeat('.').take(width));
+        input.push_str("#\n");
+    }
+
+    input.extend(core::iter::repeat('#').take(width));
+    input.push_str(".#\n");
+
+    let (part1, part2) = Day24
+        .solve_with(&bump, &input, Parts::BOTH, Params::NONE)
+        .unwrap();
+
+    assert_eq!(part1, Answer::Int(width as u64 + height as u64));
+    assert_eq!(part2, Answer::Int(3 * (width as u64 + height as u64)));
+}
+
+#[test]
+fn incomplete_basins() {
+    let bump = Bump::new();
+
+    for input in [
+        "",
+        "#.#####\n",
+        "#.#\n#.#\n",
+        "##\n##\n##\n",
+        "#.######\n#>>.<^<#\n#.<..<<#\n",
+        "#.######\n#>>.<^<#\n######.#\n########\n",
+    ] {
+        assert!(parse(&bump, input).is_err(), "{input:?}");
+    }
+}
+
+#[test]
+fn no_way_through() {
+    let bump = Bump::new();
+
+    // The blizzards fill the column under the start, whatever the time
+    let input = "#.###\n#v..#\n#v..#\n###.#\n";
+
+    assert_eq!(
+        Day24.solve_with(&bump, input, Parts::BOTH, Params::NONE),
+        Ok((Answer::None, Answer::None))
+    );
 }
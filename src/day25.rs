@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use bumpalo::collections::String;
 use bumpalo::Bump;
 
@@ -7,18 +9,40 @@ use nom::combinator::*;
 use nom::multi::*;
 use nom::sequence::*;
 
+use crate::parse::{fold_all, parse_all, IResult, ParseError};
+use crate::{run_phases, Answer, Answers, Example, Params, Parts, Phase, Solution};
+
+const DAY: u8 = 25;
+
+pub fn day25<'bump>(bump: &'bump Bump, input: &str) -> Result<Answers<'bump>, ParseError> {
+    let sum = parse(input)?;
+
+    Ok((part1(bump, &sum), part2(&sum)))
+}
+
+/// Sum of the fuel requirements
+pub type Model = isize;
+
 #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
-pub fn day25<'bump>(bump: &'bump Bump, input: &str) -> (&'bump str, usize) {
-    let sum = fold_many0(terminated(snafu_parse, line_ending), || 0, |sum, x| sum + x)(input)
-        .unwrap()
-        .1;
+pub fn parse(input: &str) -> Result<Model, ParseError> {
+    parse_all(
+        DAY,
+        input,
+        fold_all(terminated(snafu_parse, line_ending), || 0, |sum, x| sum + x),
+    )
+}
 
-    let part1 = snafu_from(bump, sum);
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+pub fn part1<'bump>(bump: &'bump Bump, &sum: &Model) -> Answer<'bump> {
+    Answer::Str(snafu_from(bump, sum))
+}
 
-    (part1, 0)
+/// The last day has no second part
+pub fn part2(_sum: &Model) -> Answer<'static> {
+    Answer::None
 }
 
-fn snafu_parse(input: &str) -> nom::IResult<&str, isize, ()> {
+fn snafu_parse(input: &str) -> IResult<&str, isize> {
     fold_many1(
         alt((
             map(char('2'), |_| 2),
@@ -60,6 +84,58 @@ fn snafu_from(bump: &Bump, mut input: isize) -> &str {
     out.into_bump_str()
 }
 
+const EXAMPLES: &[Example] = &[Example {
+    input: r#"1=-0-2
+12111
+2=0=
+21
+2=01
+111
+20012
+112
+1=-1=
+1-12
+12
+1=
+122
+"#,
+    params: Params::NONE,
+    part1: Some("2=-1=0"),
+    part2: None,
+}];
+
+pub struct Day25;
+
+impl Solution for Day25 {
+    fn day(&self) -> u8 {
+        DAY
+    }
+
+    fn title(&self) -> &'static str {
+        "Full of Hot Air"
+    }
+
+    fn needs_allocator(&self) -> bool {
+        true
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(name = "day25", skip_all))]
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        _params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError> {
+        run_phases(parts, phase, || parse(input), |sum| part1(bump, sum), part2)
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        EXAMPLES
+    }
+}
+
 #[test]
 fn snafu_parse_test() {
     assert_eq!(snafu_parse("1"), Ok(("", 1)));
@@ -102,21 +178,5 @@ fn snafu_from_test() {
 
 #[test]
 fn both_parts() {
-    let bump = bumpalo::Bump::new();
-    let example = r#"1=-0-2
-12111
-2=0=
-21
-2=01
-111
-20012
-112
-1=-1=
-1-12
-12
-1=
-122
-"#;
-    assert_eq!(day25(&bump, example).0, "2=-1=0");
-    assert_eq!(day25(&bump, example).1, 0);
+    crate::check_examples(&Day25);
 }
@@ -0,0 +1,418 @@
+//! A 2D grid of cells stored row after row in one allocation
+
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+use core::fmt::{self, Display};
+use core::ops::{Index, IndexMut, Range, RangeFrom, RangeTo};
+
+use nom::error::ParseError;
+use nom::{Compare, IResult, InputIter, InputLength, Parser, Slice};
+
+use crate::nom_extra::grid_in;
+
+/// Cells addressed by `(x, y)`, `x` growing to the right and `y` downwards
+#[derive(Debug, Clone)]
+pub struct Grid<T, A: Allocator> {
+    cells: Vec<T, A>,
+    width: usize,
+    height: usize,
+}
+
+impl<T, A: Allocator> Grid<T, A> {
+    /// A grid filled with `value`
+    pub fn new_in(width: usize, height: usize, value: T, alloc: A) -> Self
+    where
+        T: Clone,
+    {
+        let mut cells = Vec::with_capacity_in(width * height, alloc);
+        cells.resize(width * height, value);
+
+        Grid {
+            cells,
+            width,
+            height,
+        }
+    }
+
+    /// The grid of the cells given row after row
+    ///
+    /// Panics when they don't fill whole rows of `width`
+    pub fn from_vec(cells: Vec<T, A>, width: usize) -> Self {
+        let height = match width {
+            0 => 0,
+            _ => cells.len() / width,
+        };
+        assert_eq!(cells.len(), width * height, "incomplete last row");
+
+        Grid {
+            cells,
+            width,
+            height,
+        }
+    }
+
+    /// Parser of a grid with a cell for each item parsed by `cell`, one row
+    /// per line, all of the same width
+    ///
+    /// Lines end with `\n` or `\r\n`, the last one possibly with neither, and
+    /// `cell` mustn't accept them.
+    pub fn parser<I, E, F>(alloc: A, cell: F) -> impl FnMut(I) -> IResult<I, Self, E>
+    where
+        A: Copy,
+        I: Clone + InputLength + InputIter + Compare<&'static str>,
+        I: Slice<Range<usize>> + Slice<RangeFrom<usize>> + Slice<RangeTo<usize>>,
+        F: Parser<I, T, E>,
+        E: ParseError<I>,
+    {
+        let mut grid = grid_in(alloc, cell);
+
+        move |input| {
+            let (input, (cells, width)) = grid(input)?;
+
+            Ok((input, Grid::from_vec(cells, width)))
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// All the cells, row after row
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn cells_mut(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x < self.width && y < self.height {
+            Some(&self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if x < self.width && y < self.height {
+            Some(&mut self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Each cell with its position, row after row
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| ((i % width, i / width), cell))
+    }
+
+    /// Position of the first cell, row after row, accepted by `predicate`
+    pub fn position(&self, predicate: impl FnMut(&T) -> bool) -> Option<(usize, usize)> {
+        let i = self.cells.iter().position(predicate)?;
+
+        Some((i % self.width, i / self.width))
+    }
+
+    /// Positions within the grid above, below, left and right of `(x, y)`
+    pub fn neighbours4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        self.neighbours(x, y, &[(0, -1), (-1, 0), (1, 0), (0, 1)])
+    }
+
+    /// Positions within the grid around `(x, y)`, diagonals included
+    pub fn neighbours8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        #[rustfmt::skip]
+        const AROUND: &[(isize, isize)] = &[
+            (-1, -1), (0, -1), (1, -1),
+            (-1, 0),           (1, 0),
+            (-1, 1),  (0, 1),  (1, 1),
+        ];
+
+        self.neighbours(x, y, AROUND)
+    }
+
+    fn neighbours(
+        &self,
+        x: usize,
+        y: usize,
+        deltas: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = (self.width, self.height);
+
+        deltas.iter().filter_map(move |&(dx, dy)| {
+            let x = x.checked_add_signed(dx).filter(|&x| x < width)?;
+            let y = y.checked_add_signed(dy).filter(|&y| y < height)?;
+
+            Some((x, y))
+        })
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+        &mut self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    /// The rows, from the top
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[T]> + ExactSizeIterator {
+        // Chunks of 0 items aren't allowed, and there are no rows anyway
+        self.cells[..self.width * self.height].chunks_exact(self.width.max(1))
+    }
+
+    /// The cells of column `x`, from the top
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        assert!(
+            x < self.width,
+            "column {x} out of a grid of width {}",
+            self.width
+        );
+
+        self.cells[x..].iter().step_by(self.width)
+    }
+
+    /// The columns, from the left
+    pub fn columns(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = impl DoubleEndedIterator<Item = &T>> + ExactSizeIterator
+    {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    /// The grid flipped over its diagonal, rows becoming columns
+    pub fn transposed(&self) -> Self
+    where
+        T: Clone,
+        A: Clone,
+    {
+        self.remapped(self.height, self.width, |x, y| (y, x))
+    }
+
+    /// The grid rotated a quarter turn clockwise
+    pub fn rotated_right(&self) -> Self
+    where
+        T: Clone,
+        A: Clone,
+    {
+        let last_row = self.height.saturating_sub(1);
+
+        self.remapped(self.height, self.width, |x, y| (y, last_row - x))
+    }
+
+    /// The grid rotated a quarter turn counterclockwise
+    pub fn rotated_left(&self) -> Self
+    where
+        T: Clone,
+        A: Clone,
+    {
+        let last_column = self.width.saturating_sub(1);
+
+        self.remapped(self.height, self.width, |x, y| (last_column - y, x))
+    }
+
+    /// A grid of `width` and `height` where `(x, y)` is this grid's cell at
+    /// `from(x, y)`
+    fn remapped(
+        &self,
+        width: usize,
+        height: usize,
+        from: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Self
+    where
+        T: Clone,
+        A: Clone,
+    {
+        let mut cells = Vec::with_capacity_in(width * height, self.cells.allocator().clone());
+
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(self[from(x, y)].clone());
+            }
+        }
+
+        Grid {
+            cells,
+            width,
+            height,
+        }
+    }
+
+    /// Mirror the grid left to right
+    pub fn flip_horizontal(&mut self) {
+        for y in 0..self.height {
+            self.row_mut(y).reverse();
+        }
+    }
+
+    /// Mirror the grid top to bottom
+    pub fn flip_vertical(&mut self) {
+        for y in 0..self.height / 2 {
+            let (top, bottom) = self.cells.split_at_mut((self.height - 1 - y) * self.width);
+            top[y * self.width..(y + 1) * self.width].swap_with_slice(&mut bottom[..self.width]);
+        }
+    }
+}
+
+impl<T, A: Allocator> Index<(usize, usize)> for Grid<T, A> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        assert!(
+            x < self.width,
+            "x {x} out of a grid of width {}",
+            self.width
+        );
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T, A: Allocator> IndexMut<(usize, usize)> for Grid<T, A> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        assert!(
+            x < self.width,
+            "x {x} out of a grid of width {}",
+            self.width
+        );
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+impl<T: PartialEq, A: Allocator> PartialEq for Grid<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width && self.cells == other.cells
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for Grid<T, A> {}
+
+/// One line per row
+impl<T: Display, A: Allocator> Display for Grid<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                cell.fmt(f)?;
+            }
+
+            f.write_str("\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn example(bump: &bumpalo::Bump) -> Grid<char, &bumpalo::Bump> {
+    use nom::character::complete::none_of;
+
+    Grid::parser::<_, crate::parse::Error<_>, _>(bump, none_of("\r\n"))("abc\r\ndef\n")
+        .unwrap()
+        .1
+}
+
+#[test]
+fn access() {
+    let bump = bumpalo::Bump::new();
+    let mut grid = example(&bump);
+
+    assert_eq!((grid.width(), grid.height()), (3, 2));
+    assert_eq!(grid.get(2, 0), Some(&'c'));
+    assert_eq!(grid.get(0, 1), Some(&'d'));
+    assert_eq!(grid.get(3, 0), None);
+    assert_eq!(grid.get(0, 2), None);
+    assert_eq!(grid[(1, 1)], 'e');
+    assert_eq!(grid.position(|&c| c == 'f'), Some((2, 1)));
+
+    *grid.get_mut(1, 0).unwrap() = 'B';
+    grid[(2, 1)] = 'F';
+    assert_eq!(grid.to_string(), "aBc\ndeF\n");
+    assert_eq!(grid.get_mut(0, 5), None);
+
+    let filled = Grid::new_in(2, 3, 0u8, &bump);
+    assert_eq!(filled.cells(), &[0; 6]);
+    assert_eq!(filled.rows().len(), 3);
+}
+
+#[test]
+fn neighbours() {
+    let bump = bumpalo::Bump::new();
+    let grid = example(&bump);
+    let collect = |it: &mut dyn Iterator<Item = (usize, usize)>| it.collect::<Vec<_>>();
+
+    assert_eq!(collect(&mut grid.neighbours4(0, 0)), [(1, 0), (0, 1)]);
+    assert_eq!(
+        collect(&mut grid.neighbours4(1, 1)),
+        [(1, 0), (0, 1), (2, 1)]
+    );
+    assert_eq!(
+        collect(&mut grid.neighbours8(2, 1)),
+        [(1, 0), (2, 0), (1, 1)]
+    );
+    assert_eq!(grid.neighbours8(1, 0).count(), 5);
+}
+
+#[test]
+fn rows_and_columns() {
+    let bump = bumpalo::Bump::new();
+    let grid = example(&bump);
+
+    assert_eq!(
+        grid.rows().collect::<Vec<_>>(),
+        [&['a', 'b', 'c'][..], &['d', 'e', 'f'][..]]
+    );
+    assert_eq!(grid.row(1), ['d', 'e', 'f']);
+    assert_eq!(grid.column(1).collect::<Vec<_>>(), [&'b', &'e']);
+    assert_eq!(
+        grid.columns()
+            .rev()
+            .map(|column| column.rev().collect::<String>())
+            .collect::<Vec<_>>(),
+        ["fc", "eb", "da"]
+    );
+    assert_eq!(grid.iter().last(), Some(((2, 1), &'f')));
+}
+
+#[test]
+fn transformations() {
+    let bump = bumpalo::Bump::new();
+    let grid = example(&bump);
+
+    assert_eq!(grid.transposed().to_string(), "ad\nbe\ncf\n");
+    assert_eq!(grid.rotated_right().to_string(), "da\neb\nfc\n");
+    assert_eq!(grid.rotated_left().to_string(), "cf\nbe\nad\n");
+    assert_eq!(grid.rotated_right().rotated_left(), grid);
+
+    let mut flipped = grid.clone();
+    flipped.flip_horizontal();
+    assert_eq!(flipped.to_string(), "cba\nfed\n");
+    flipped.flip_vertical();
+    assert_eq!(flipped.to_string(), "fed\ncba\n");
+    assert_eq!(flipped, grid.rotated_right().rotated_right());
+
+    let mut cells = Vec::new_in(&bump);
+    cells.extend([1, 2, 3]);
+    let mut odd = Grid::from_vec(cells, 1);
+    odd.flip_vertical();
+    assert_eq!(odd.cells(), [3, 2, 1]);
+}
+
+#[test]
+fn parse_errors() {
+    use nom::character::complete::one_of;
+
+    let bump = bumpalo::Bump::new();
+    let mut parser = Grid::parser::<_, crate::parse::Error<_>, _>(&bump, one_of("#."));
+
+    assert!(parser(&b"#.\n.#"[..]).is_ok());
+    assert!(parser(&b"#.\n.\n"[..]).is_err());
+    assert!(parser(&b"#.\n.x\n"[..]).is_err());
+}
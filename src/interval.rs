@@ -0,0 +1,535 @@
+//! Sets of integers stored as sorted, disjoint intervals
+
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+use core::cmp::Ordering;
+
+use num::{one, PrimInt};
+
+/// The integers from `start` to `end`, both included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interval<T> {
+    // invariant: a <= b
+    a: T,
+    b: T,
+}
+
+impl<T: PrimInt> Interval<T> {
+    /// The interval between `a` and `b`, in either order
+    pub fn new(a: T, b: T) -> Self {
+        Self {
+            a: a.min(b),
+            b: a.max(b),
+        }
+    }
+
+    pub fn start(&self) -> T {
+        self.a
+    }
+
+    pub fn end(&self) -> T {
+        self.b
+    }
+
+    /// Number of integers in the interval
+    ///
+    /// Panics when it doesn't fit in a `usize`
+    pub fn count(&self) -> usize {
+        self.b
+            .to_i128()
+            .zip(self.a.to_i128())
+            .and_then(|(b, a)| usize::try_from(b - a).ok()?.checked_add(1))
+            .expect("too many integers to count")
+    }
+
+    pub fn contains(&self, x: &T) -> bool {
+        (self.a..=self.b).contains(x)
+    }
+
+    /// Whether `other` is entirely within this interval
+    pub fn covers(&self, other: &Self) -> bool {
+        self.a <= other.a && other.b <= self.b
+    }
+
+    /// Whether the intervals have an integer in common
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.a <= other.b && other.a <= self.b
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        self.overlaps(other).then(|| Self {
+            a: self.a.max(other.a),
+            b: self.b.min(other.b),
+        })
+    }
+
+    /// Whether this interval ends before `other` starts, with a gap between
+    /// them
+    fn before(&self, other: &Self) -> bool {
+        self.b
+            .checked_add(&one())
+            .map_or(false, |next| next < other.a)
+    }
+}
+
+/// Integers as the fewest intervals covering them
+#[derive(Debug, Clone)]
+pub struct IntervalSet<T, A: Allocator> {
+    // invariant: ordered, with a gap between consecutive intervals
+    intervals: Vec<Interval<T>, A>,
+}
+
+impl<T: PrimInt, A: Allocator> IntervalSet<T, A> {
+    pub fn new_in(alloc: A) -> Self {
+        IntervalSet {
+            intervals: Vec::new_in(alloc),
+        }
+    }
+
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        IntervalSet {
+            intervals: Vec::with_capacity_in(capacity, alloc),
+        }
+    }
+
+    /// The intervals of the set, in order
+    pub fn intervals(&self) -> &[Interval<T>] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.intervals.clear();
+    }
+
+    /// Add the integers of `interval`, merging it with the intervals it
+    /// overlaps or touches
+    pub fn insert(&mut self, interval: Interval<T>) {
+        let low = self.intervals.partition_point(|x| x.before(&interval));
+        let high = self.intervals.partition_point(|x| !interval.before(x));
+
+        let merged = match &self.intervals[low..high] {
+            [] => interval,
+            [first, ..] => Interval {
+                a: interval.a.min(first.a),
+                b: interval.b.max(self.intervals[high - 1].b),
+            },
+        };
+
+        self.intervals.splice(low..high, [merged]);
+    }
+
+    /// Take out the integers of `interval`, splitting the interval around it
+    /// if need be
+    pub fn remove(&mut self, interval: Interval<T>) {
+        let low = self.intervals.partition_point(|x| x.b < interval.a);
+        let high = self.intervals.partition_point(|x| x.a <= interval.b);
+
+        if low == high {
+            return;
+        }
+
+        let (first, last) = (self.intervals[low], self.intervals[high - 1]);
+        let left = (first.a < interval.a).then(|| Interval {
+            a: first.a,
+            b: interval.a - one(),
+        });
+        let right = (interval.b < last.b).then(|| Interval {
+            a: interval.b + one(),
+            b: last.b,
+        });
+
+        self.intervals
+            .splice(low..high, left.into_iter().chain(right));
+    }
+
+    /// Keep only the integers within `bounds`
+    pub fn limit_by(&mut self, bounds: Interval<T>) {
+        let low = self.intervals.partition_point(|x| x.b < bounds.a);
+        let high = self.intervals.partition_point(|x| x.a <= bounds.b);
+
+        self.intervals.truncate(high);
+        self.intervals.drain(..low);
+
+        if let Some(first) = self.intervals.first_mut() {
+            first.a = first.a.max(bounds.a);
+        }
+
+        if let Some(last) = self.intervals.last_mut() {
+            last.b = last.b.min(bounds.b);
+        }
+    }
+
+    /// Whether `x` is in the set, by a binary search of the intervals
+    pub fn contains(&self, x: &T) -> bool {
+        self.intervals
+            .binary_search_by(|interval| {
+                if interval.b < *x {
+                    Ordering::Less
+                } else if interval.a > *x {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .is_ok()
+    }
+
+    /// Number of integers in the set
+    pub fn count(&self) -> usize {
+        self.intervals.iter().map(|i| i.count()).sum()
+    }
+
+    /// The intervals missing between the first and last integers of the set
+    pub fn gaps(&self) -> impl Iterator<Item = Interval<T>> + '_ {
+        self.intervals.windows(2).map(|pair| Interval {
+            a: pair[0].b + one(),
+            b: pair[1].a - one(),
+        })
+    }
+
+    /// The integers within `bounds` missing from the set
+    pub fn complement(&self, bounds: Interval<T>) -> Self
+    where
+        A: Clone,
+    {
+        let mut complement = Self::new_in(self.intervals.allocator().clone());
+        // Start of the integers not yet covered, if any are left
+        let mut next = Some(bounds.a);
+
+        for interval in self.intervals.iter().filter(|x| x.overlaps(&bounds)) {
+            match next {
+                Some(a) if a < interval.a => complement.intervals.push(Interval {
+                    a,
+                    b: interval.a - one(),
+                }),
+                _ => {}
+            }
+
+            next = interval.b.checked_add(&one());
+        }
+
+        match next {
+            Some(a) if a <= bounds.b => complement.intervals.push(Interval { a, b: bounds.b }),
+            _ => {}
+        }
+
+        complement
+    }
+
+    /// The integers in either set
+    pub fn union(&self, other: &Self) -> Self
+    where
+        A: Clone,
+    {
+        let mut union = Self::with_capacity_in(
+            self.intervals.len() + other.intervals.len(),
+            self.intervals.allocator().clone(),
+        );
+        let (mut lhs, mut rhs) = (
+            self.intervals.iter().peekable(),
+            other.intervals.iter().peekable(),
+        );
+
+        // Merge the intervals by their start, joining each to the last one
+        // when they overlap or touch
+        loop {
+            let interval = match (lhs.peek(), rhs.peek()) {
+                (Some(l), Some(r)) if l.a <= r.a => lhs.next(),
+                (Some(_), Some(_)) => rhs.next(),
+                (Some(_), None) => lhs.next(),
+                (None, _) => rhs.next(),
+            };
+
+            let Some(&interval) = interval else {
+                break;
+            };
+
+            match union.intervals.last_mut() {
+                Some(last) if !last.before(&interval) => last.b = last.b.max(interval.b),
+                _ => union.intervals.push(interval),
+            }
+        }
+
+        union
+    }
+
+    /// The integers in both sets
+    pub fn intersection(&self, other: &Self) -> Self
+    where
+        A: Clone,
+    {
+        let mut intersection = Self::new_in(self.intervals.allocator().clone());
+        let (mut i, mut j) = (0, 0);
+
+        while let (Some(l), Some(r)) = (self.intervals.get(i), other.intervals.get(j)) {
+            intersection.intervals.extend(l.intersection(r));
+
+            // The interval ending first can't meet any other
+            if l.b < r.b {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        intersection
+    }
+
+    /// The integers in this set but not in `other`
+    pub fn difference(&self, other: &Self) -> Self
+    where
+        A: Clone,
+    {
+        let mut difference = Self::new_in(self.intervals.allocator().clone());
+        let mut j = 0;
+
+        for interval in self.intervals.iter() {
+            // Skip the intervals of `other` ending before this one
+            while other.intervals.get(j).map_or(false, |r| r.b < interval.a) {
+                j += 1;
+            }
+
+            // Start of what is left of the interval, if anything
+            let mut next = Some(interval.a);
+
+            for r in other.intervals[j..]
+                .iter()
+                .take_while(|r| r.a <= interval.b)
+            {
+                match next {
+                    Some(a) if a < r.a => difference.intervals.push(Interval { a, b: r.a - one() }),
+                    _ => {}
+                }
+
+                next = (r.b < interval.b).then(|| r.b + one());
+            }
+
+            if let Some(a) = next {
+                difference.intervals.push(Interval { a, b: interval.b });
+            }
+        }
+
+        difference
+    }
+}
+
+impl<T: PartialEq, A: Allocator> PartialEq for IntervalSet<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.intervals[..] == other.intervals[..]
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for IntervalSet<T, A> {}
+
+#[cfg(test)]
+fn set<'a, T: PrimInt>(
+    bump: &'a bumpalo::Bump,
+    intervals: &[(T, T)],
+) -> IntervalSet<T, &'a bumpalo::Bump> {
+    let mut set = IntervalSet::new_in(bump);
+
+    for &(a, b) in intervals {
+        set.insert(Interval::new(a, b));
+    }
+
+    set
+}
+
+#[test]
+fn intervals() {
+    let bump = bumpalo::Bump::new();
+    let mut set = IntervalSet::new_in(&bump);
+
+    set.insert(Interval::new(1, 3));
+    assert_eq!(set.intervals(), &[Interval::new(1, 3)]);
+
+    set.insert(Interval::new(5, 5));
+    assert_eq!(set.intervals(), &[Interval::new(1, 3), Interval::new(5, 5)]);
+
+    set.insert(Interval::new(-3, -1));
+    assert_eq!(
+        set.intervals(),
+        &[
+            Interval::new(-3, -1),
+            Interval::new(1, 3),
+            Interval::new(5, 5)
+        ]
+    );
+
+    set.insert(Interval::new(1, 2));
+    assert_eq!(
+        set.intervals(),
+        &[
+            Interval::new(-3, -1),
+            Interval::new(1, 3),
+            Interval::new(5, 5)
+        ]
+    );
+
+    set.insert(Interval::new(-1, 2));
+    assert_eq!(
+        set.intervals(),
+        &[Interval::new(-3, 3), Interval::new(5, 5)]
+    );
+
+    // Touching intervals are merged
+    set.insert(Interval::new(4, 4));
+    assert_eq!(set.intervals(), &[Interval::new(-3, 5)]);
+
+    set.insert(Interval::new(7, 8));
+    set.insert(Interval::new(-3, 10));
+    assert_eq!(set.intervals(), &[Interval::new(-3, 10)]);
+
+    set.insert(Interval::new(15, 10));
+    assert_eq!(set.intervals(), &[Interval::new(-3, 15)]);
+    assert_eq!(set.count(), 19);
+}
+
+#[test]
+fn removal() {
+    let bump = bumpalo::Bump::new();
+    let mut set = set(&bump, &[(0, 10), (20, 30)]);
+
+    set.remove(Interval::new(11, 19));
+    assert_eq!(set, self::set(&bump, &[(0, 10), (20, 30)]));
+
+    set.remove(Interval::new(3, 5));
+    assert_eq!(set, self::set(&bump, &[(0, 2), (6, 10), (20, 30)]));
+
+    set.remove(Interval::new(8, 25));
+    assert_eq!(set, self::set(&bump, &[(0, 2), (6, 7), (26, 30)]));
+
+    set.remove(Interval::new(-5, 0));
+    set.remove(Interval::new(30, 35));
+    assert_eq!(set, self::set(&bump, &[(1, 2), (6, 7), (26, 29)]));
+
+    set.remove(Interval::new(0, 40));
+    assert!(set.is_empty());
+}
+
+#[test]
+fn membership() {
+    let bump = bumpalo::Bump::new();
+    let set = set(&bump, &[(-10, -5), (0, 0), (3, 8), (20, 21)]);
+
+    let members: Vec<i32> = (-12..24).filter(|x| set.contains(x)).collect();
+    let expected: Vec<i32> = [-10..=-5, 0..=0, 3..=8, 20..=21]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    assert_eq!(members, expected);
+    assert_eq!(set.count(), expected.len());
+    assert!(!IntervalSet::<i32, _>::new_in(&bump).contains(&0));
+}
+
+#[test]
+fn bounds() {
+    let bump = bumpalo::Bump::new();
+    let mut set = set(&bump, &[(-10, -5), (0, 4), (8, 12), (20, 21)]);
+
+    assert_eq!(
+        set.gaps().collect::<Vec<_>>(),
+        &[
+            Interval::new(-4, -1),
+            Interval::new(5, 7),
+            Interval::new(13, 19)
+        ]
+    );
+
+    assert_eq!(
+        set.complement(Interval::new(-20, 30)),
+        self::set(&bump, &[(-20, -11), (-4, -1), (5, 7), (13, 19), (22, 30)])
+    );
+    assert_eq!(
+        set.complement(Interval::new(2, 10)),
+        self::set(&bump, &[(5, 7)])
+    );
+    assert_eq!(
+        set.complement(Interval::new(14, 15)),
+        self::set(&bump, &[(14, 15)])
+    );
+
+    set.limit_by(Interval::new(-7, 10));
+    assert_eq!(set, self::set(&bump, &[(-7, -5), (0, 4), (8, 10)]));
+
+    set.limit_by(Interval::new(5, 7));
+    assert!(set.is_empty());
+    assert_eq!(set.gaps().count(), 0);
+}
+
+#[test]
+fn set_operations() {
+    let bump = bumpalo::Bump::new();
+    let lhs = set(&bump, &[(0, 5), (10, 15), (20, 25)]);
+    let rhs = set(&bump, &[(3, 11), (16, 17), (22, 22), (30, 31)]);
+
+    assert_eq!(lhs.union(&rhs), set(&bump, &[(0, 17), (20, 25), (30, 31)]));
+    assert_eq!(
+        lhs.intersection(&rhs),
+        set(&bump, &[(3, 5), (10, 11), (22, 22)])
+    );
+    assert_eq!(
+        lhs.difference(&rhs),
+        set(&bump, &[(0, 2), (12, 15), (20, 21), (23, 25)])
+    );
+    assert_eq!(
+        rhs.difference(&lhs),
+        set(&bump, &[(6, 9), (16, 17), (30, 31)])
+    );
+
+    // Against a plain membership test of each integer
+    let (union, intersection, difference) = (
+        lhs.union(&rhs),
+        lhs.intersection(&rhs),
+        lhs.difference(&rhs),
+    );
+
+    for x in -5..40 {
+        let (l, r) = (lhs.contains(&x), rhs.contains(&x));
+
+        assert_eq!(union.contains(&x), l || r);
+        assert_eq!(intersection.contains(&x), l && r);
+        assert_eq!(difference.contains(&x), l && !r);
+    }
+
+    let empty = IntervalSet::new_in(&bump);
+    assert_eq!(lhs.union(&empty), lhs);
+    assert_eq!(lhs.intersection(&empty), empty);
+    assert_eq!(lhs.difference(&empty), lhs);
+    assert_eq!(empty.difference(&lhs), empty);
+}
+
+#[test]
+fn type_limits() {
+    let bump = bumpalo::Bump::new();
+    let mut set = set(&bump, &[(0u8, 10), (250, 255)]);
+
+    set.insert(Interval::new(11, 20));
+    assert_eq!(
+        set.intervals(),
+        &[Interval::new(0, 20), Interval::new(250, 255)]
+    );
+    assert_eq!(set.count(), 27);
+    assert_eq!(
+        set.complement(Interval::new(0, 255)),
+        self::set(&bump, &[(21, 249)])
+    );
+
+    set.remove(Interval::new(0, 0));
+    set.remove(Interval::new(255, 255));
+    assert_eq!(set, self::set(&bump, &[(1, 20), (250, 254)]));
+
+    let full = self::set(&bump, &[(i64::MIN, i64::MAX)]);
+    assert!(full
+        .complement(Interval::new(i64::MIN, i64::MAX))
+        .is_empty());
+    assert_eq!(
+        full.difference(&self::set(&bump, &[(0, 0)])),
+        self::set(&bump, &[(i64::MIN, -1), (1, i64::MAX)])
+    );
+}
@@ -1,10 +1,15 @@
 #![warn(clippy::all)]
 #![feature(allocator_api)]
 #![feature(iter_collect_into)]
-// #![no_std]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
 extern crate alloc;
 
+use bumpalo::Bump;
+use core::fmt::{self, Display};
+use core::ops::RangeInclusive;
+use parse::ParseError;
+
 pub mod day01;
 pub mod day02;
 pub mod day03;
@@ -24,9 +29,348 @@ pub mod day18;
 pub mod day24;
 pub mod day25;
 
-#[allow(dead_code)]
 pub(crate) mod bitset;
+pub mod grid;
 #[allow(dead_code)]
 pub(crate) mod hash;
-#[allow(dead_code)]
-pub(crate) mod nom_extra;
+pub mod interval;
+pub mod nom_extra;
+pub mod parse;
+pub mod pathfinding;
+
+/// Answer to one part of a puzzle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Answer<'a> {
+    /// The part has no answer, like day 25's part 2, or wasn't solved
+    None,
+    Int(u64),
+    /// Text on a single line
+    Str(&'a str),
+    /// Picture drawn over several lines, like day 10's CRT
+    Art(&'a str),
+}
+
+impl Answer<'_> {
+    pub fn is_none(&self) -> bool {
+        *self == Answer::None
+    }
+}
+
+/// Displays nothing for [`Answer::None`], and art without a leading or
+/// trailing newline
+impl Display for Answer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::None => Ok(()),
+            Answer::Int(n) => n.fmt(f),
+            Answer::Str(s) => f.write_str(s),
+            Answer::Art(art) => f.write_str(art.trim_matches('\n')),
+        }
+    }
+}
+
+macro_rules! answer_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer<'_> {
+                fn from(n: $t) -> Self {
+                    Answer::Int(n as u64)
+                }
+            }
+        )*
+    };
+}
+
+answer_from_int!(u8, u16, u32, u64, usize);
+
+/// Both answers of a day, borrowing from the arena passed to
+/// [`Solution::solve`]
+pub type Answers<'a> = (Answer<'a>, Answer<'a>);
+
+/// Which parts of a puzzle to solve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parts {
+    pub part1: bool,
+    pub part2: bool,
+}
+
+impl Parts {
+    pub const NONE: Parts = Parts {
+        part1: false,
+        part2: false,
+    };
+    pub const PART1: Parts = Parts {
+        part1: true,
+        part2: false,
+    };
+    pub const PART2: Parts = Parts {
+        part1: false,
+        part2: true,
+    };
+    pub const BOTH: Parts = Parts {
+        part1: true,
+        part2: true,
+    };
+
+    pub fn union(self, other: Parts) -> Parts {
+        Parts {
+            part1: self.part1 || other.part1,
+            part2: self.part2 || other.part2,
+        }
+    }
+
+    pub fn is_empty(self) -> bool {
+        self == Parts::NONE
+    }
+}
+
+/// Numeric parameters of a puzzle which aren't part of its input, by name
+///
+/// Examples can be smaller than the real puzzle, for instance day 15 looks at
+/// row 10 of its example instead of row 2000000
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Params<'a>(pub &'a [(&'a str, i64)]);
+
+impl Params<'_> {
+    pub const NONE: Params<'static> = Params(&[]);
+
+    /// Value of `param`, `default` when it isn't set, and `None` when it is
+    /// outside of the values `param` accepts or doesn't fit in a `T`
+    pub fn get<T: TryFrom<i64>>(&self, param: &Param, default: T) -> Option<T> {
+        match self.0.iter().find(|(n, _)| *n == param.name) {
+            Some((_, value)) if param.values.contains(value) => T::try_from(*value).ok(),
+            Some(_) => None,
+            None => Some(default),
+        }
+    }
+}
+
+/// A parameter a puzzle reads from [`Params`], with the values it accepts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub name: &'static str,
+    pub values: RangeInclusive<i64>,
+}
+
+/// Declare the `Config` of a day's parameters, with the puzzle's values as
+/// defaults and the values each one accepts
+///
+/// ```ignore
+/// day_config! {
+///     /// Distinct characters making a start-of-packet marker
+///     packet_marker: usize = 4, in 1..=26;
+/// }
+/// ```
+#[macro_export]
+macro_rules! day_config {
+    ($($(#[doc = $doc:literal])* $name:ident: $ty:ty = $default:expr, in $values:expr;)*) => {
+        /// Parameters of the puzzle, the defaults being the puzzle's
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct Config {
+            $($(#[doc = $doc])* pub $name: $ty,)*
+        }
+
+        impl Default for Config {
+            fn default() -> Self {
+                Config {
+                    $($name: $default,)*
+                }
+            }
+        }
+
+        impl Config {
+            pub const PARAMS: &'static [$crate::Param] = &[$($crate::Param {
+                name: stringify!($name),
+                values: $values,
+            },)*];
+
+            /// The config from `params`, an error for day `day` when one of
+            /// them isn't within the values of [`Self::PARAMS`]
+            pub fn from_params(
+                day: u8,
+                params: $crate::Params,
+            ) -> Result<Config, $crate::parse::ParseError> {
+                let default = Config::default();
+                let param = |name| Config::PARAMS.iter().find(|p| p.name == name).unwrap();
+
+                Ok(Config {
+                    $($name: params
+                        .get(param(stringify!($name)), default.$name)
+                        .ok_or_else(|| {
+                            $crate::parse::ParseError::param(day, param(stringify!($name)))
+                        })?,)*
+                })
+            }
+        }
+    };
+}
+
+/// Phases of solving a puzzle, which are timed separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Turning the input into the day's model
+    Parse,
+    Part1,
+    Part2,
+}
+
+/// An example from a puzzle's description, with its expected answers
+#[derive(Debug, Clone, Copy)]
+pub struct Example {
+    pub input: &'static str,
+    pub params: Params<'static>,
+    /// Answers as displayed, `None` when the example doesn't give one
+    pub part1: Option<&'static str>,
+    pub part2: Option<&'static str>,
+}
+
+/// A solution to one day of the calendar
+pub trait Solution: Sync {
+    /// Day of December this solves, starting at 1
+    fn day(&self) -> u8;
+
+    /// Title of the puzzle
+    fn title(&self) -> &'static str;
+
+    /// Whether `solve` uses the arena for more than storing its answers
+    fn needs_allocator(&self) -> bool;
+
+    /// The [`Params`] the puzzle reads, the others being ignored
+    fn params(&self) -> &'static [Param] {
+        &[]
+    }
+
+    /// Solve the requested parts with the given parameters, calling `phase`
+    /// as each [`Phase`] starts
+    ///
+    /// The answer of a part which isn't requested is [`Answer::None`].
+    fn solve_phases<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        params: Params,
+        phase: &mut dyn FnMut(Phase),
+    ) -> Result<Answers<'bump>, ParseError>;
+
+    /// Solve both parts of the puzzle for the given input
+    fn solve<'bump>(&self, bump: &'bump Bump, input: &str) -> Result<Answers<'bump>, ParseError> {
+        self.solve_with(bump, input, Parts::BOTH, Params::NONE)
+    }
+
+    /// Solve only some parts of the puzzle, with the given parameters
+    fn solve_with<'bump>(
+        &self,
+        bump: &'bump Bump,
+        input: &str,
+        parts: Parts,
+        params: Params,
+    ) -> Result<Answers<'bump>, ParseError> {
+        self.solve_phases(bump, input, parts, params, &mut |_| {})
+    }
+
+    /// Examples from the puzzle's description
+    fn examples(&self) -> &'static [Example] {
+        &[]
+    }
+}
+
+/// All the solved days, in order
+pub static SOLUTIONS: &[&dyn Solution] = &[
+    &day01::Day01,
+    &day02::Day02,
+    &day03::Day03,
+    &day04::Day04,
+    &day05::Day05,
+    &day06::Day06,
+    &day07::Day07,
+    &day08::Day08,
+    &day09::Day09,
+    &day10::Day10,
+    &day11::Day11,
+    &day12::Day12,
+    &day13::Day13,
+    &day14::Day14,
+    &day15::Day15,
+    &day18::Day18,
+    &day24::Day24,
+    &day25::Day25,
+];
+
+/// Look up the solution for a given day
+pub fn solution(day: u8) -> Option<&'static dyn Solution> {
+    SOLUTIONS.iter().copied().find(|s| s.day() == day)
+}
+
+/// Parse the input into a model, then solve the requested parts from it
+///
+/// The parts may borrow their answers for different lifetimes, from the arena
+/// or `'static` ones.
+pub(crate) fn run_phases<'bump, 'a: 'bump, 'b: 'bump, M>(
+    parts: Parts,
+    phase: &mut dyn FnMut(Phase),
+    parse: impl FnOnce() -> Result<M, ParseError>,
+    part1: impl FnOnce(&M) -> Answer<'a>,
+    part2: impl FnOnce(&M) -> Answer<'b>,
+) -> Result<Answers<'bump>, ParseError> {
+    phase(Phase::Parse);
+    let model = parse()?;
+
+    let mut answers = (Answer::None, Answer::None);
+
+    if parts.part1 {
+        phase(Phase::Part1);
+        answers.0 = part1(&model);
+    }
+
+    if parts.part2 {
+        phase(Phase::Part2);
+        answers.1 = part2(&model);
+    }
+
+    Ok(answers)
+}
+
+/// Check that a solution gives the expected answers on all its examples
+#[cfg(test)]
+pub(crate) fn check_examples(solution: &dyn Solution) {
+    let bump = Bump::new();
+
+    assert!(!solution.examples().is_empty());
+
+    for example in solution.examples() {
+        for (name, value) in example.params.0 {
+            let param = solution.params().iter().find(|param| param.name == *name);
+            assert!(param.unwrap().values.contains(value), "{name} = {value}");
+        }
+
+        let (part1, part2) = solution
+            .solve_with(&bump, example.input, Parts::BOTH, example.params)
+            .unwrap();
+
+        if let Some(expected) = example.part1 {
+            assert_eq!(part1.to_string().trim(), expected, "{example:?}");
+        }
+
+        if let Some(expected) = example.part2 {
+            assert_eq!(part2.to_string().trim(), expected, "{example:?}");
+        }
+    }
+}
+
+#[test]
+fn answers() {
+    assert_eq!(Answer::from(42u16), Answer::Int(42));
+    assert_eq!(Answer::Int(13029714573243).to_string(), "13029714573243");
+    assert_eq!(Answer::Str("CMZ").to_string(), "CMZ");
+    assert_eq!(Answer::Art("\n#.\n.#\n").to_string(), "#.\n.#");
+    assert_eq!(Answer::None.to_string(), "");
+    assert!(Answer::None.is_none());
+    assert_ne!(Answer::Str("42"), Answer::Int(42));
+}
+
+#[test]
+fn registry() {
+    assert!(SOLUTIONS.windows(2).all(|w| w[0].day() < w[1].day()));
+    assert!(SOLUTIONS.iter().all(|s| (1..=25).contains(&s.day())));
+}
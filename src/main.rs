@@ -1,21 +1,48 @@
 use assert_no_alloc::*;
-use std::fmt::Write;
-use std::path::Path;
+
+mod runner;
+
+use core::num::NonZeroUsize;
+use runner::bench::{bench, print_table};
+use runner::check::check;
+use runner::cli::{usage, Options};
+use runner::config::Config;
+use runner::report::Report;
+use runner::Input;
+use std::process::ExitCode;
+use std::time::Instant;
 
 #[cfg(feature = "trace")]
 use tracing_chrome::ChromeLayerBuilder;
 #[cfg(feature = "trace")]
 use tracing_subscriber::prelude::*;
 
-#[cfg(all(debug_assertions, not(feature = "trace")))] // required when disable_release is set (default)
+#[cfg(all(debug_assertions, not(feature = "trace"), not(feature = "count-alloc")))] // required when disable_release is set (default)
 #[global_allocator]
 static A: AllocDisabler = AllocDisabler;
 
+#[cfg(feature = "count-alloc")]
+#[global_allocator]
+static A: runner::alloc_count::CountingAlloc = runner::alloc_count::CountingAlloc;
+
+// Derived from the output of `--measure-capacity`, leaving some headroom
 const ALLOCATOR_CAPACITY: usize = 380 * 1024; // bytes
 
-fn main() -> std::io::Result<()> {
+fn new_arena(capacity: usize) -> bumpalo::Bump {
+    let bump = bumpalo::Bump::with_capacity(capacity);
+    bump.set_allocation_limit(Some(0));
+    bump
+}
+
+fn main() -> ExitCode {
     #[cfg(feature = "trace")]
     let (chrome_layer, _guard) = ChromeLayerBuilder::new().build();
+    // Prints the time spent in each span when dropped, at the end of main,
+    // which is why `run` returns the exit code instead of exiting
+    #[cfg(feature = "trace")]
+    let (summary_layer, _summary_guard) = runner::span_summary::summary_layer();
+    #[cfg(feature = "trace")]
+    let (folded_layer, _folded_guard) = runner::folded::folded_layer(None);
     #[cfg(feature = "trace")]
     let fmt_subscriber = tracing_subscriber::fmt::layer()
         .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
@@ -23,120 +50,273 @@ fn main() -> std::io::Result<()> {
         .with_level(false)
         .compact();
     #[cfg(feature = "trace")]
-    tracing_subscriber::registry()
-        .with(chrome_layer)
-        .with(fmt_subscriber)
-        .init();
+    runner::alloc_count::Uncounted(
+        tracing_subscriber::registry()
+            .with(chrome_layer)
+            .with(summary_layer)
+            .with(folded_layer)
+            .with(fmt_subscriber),
+    )
+    .init();
 
     let main_span = tracing::span!(tracing::Level::TRACE, "main");
     let _enter = main_span.enter();
 
+    run()
+}
+
+fn run() -> ExitCode {
     let mut args = std::env::args();
 
     let program_name = args.next().unwrap();
-    let cli_day = if let Some(day) = args.next() {
-        match day.parse::<u8>() {
-            Ok(day) => Some(day),
-            Err(_) => {
-                print!(
-                    r#"
-Usage:
-    {program_name} [DAY] [INPUT_PATH]
-
-Defaults to all the days when none specified
-"#
-                );
-                std::process::exit(1);
-            }
+    let mut options = match Options::parse(args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}");
+            print!("{}", usage(&program_name));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let config = match Config::load(options.config.as_deref()) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            return ExitCode::FAILURE;
         }
-    } else {
-        None
     };
-    let cli_input_path = args.next();
+
+    // The command line takes precedence over the config
+    if options.input_dir.is_none() {
+        options.input_dir = config.input_dir.clone();
+    }
+
+    let capacity = config.arena_capacity.unwrap_or(ALLOCATOR_CAPACITY);
+    let new_arena = || new_arena(capacity);
+
+    if options.example {
+        if !runner::example::run_examples(&options.days, &mut new_arena()) {
+            return ExitCode::FAILURE;
+        }
+
+        return ExitCode::SUCCESS;
+    }
+
+    if options.watch {
+        runner::watch::watch(&options, &config, new_arena);
+    }
+
+    if let Some(compare_options) = &options.compare {
+        let records = match runner::history::load(options.history_path()) {
+            Ok(records) => records,
+            Err(err) => {
+                eprintln!("Error: {}: {err}", options.history_path().display());
+                return ExitCode::FAILURE;
+            }
+        };
+
+        let comparisons = runner::history::compare(&records, &options.days);
+
+        if !runner::history::print_comparisons(&comparisons, compare_options) {
+            return ExitCode::FAILURE;
+        }
+
+        return ExitCode::SUCCESS;
+    }
+
+    let input_path = |day| options.input_path(day);
 
     let io_span = tracing::span!(tracing::Level::TRACE, "Allocator / IO");
     let io_span = io_span.enter();
 
-    #[allow(unused)]
-    let mut bump = bumpalo::Bump::with_capacity(ALLOCATOR_CAPACITY);
-    bump.set_allocation_limit(Some(0));
+    let mut inputs: [Input; 25] = Default::default();
 
-    let mut contents: [&str; 25] = Default::default();
+    for solution in aoc2022::SOLUTIONS {
+        let day = solution.day();
 
-    if let Some(day) = cli_day {
-        contents[day as usize - 1] = get_content_for_day(
-            cli_input_path
-                .as_deref()
-                .unwrap_or(format!("inputs/day{day:0>2}.txt").as_str()),
-        );
-    } else {
-        for (day, content) in contents.iter_mut().enumerate() {
-            let day = day + 1;
+        if options.days.selects(day) {
             tracing::span!(tracing::Level::TRACE, "day").in_scope(|| {
-                *content = get_content_for_day(format!("inputs/day{day:0>2}.txt").as_str());
+                inputs[day as usize - 1] = Input::load(input_path(day));
             });
         }
     }
 
-    let mut report = String::with_capacity(1024);
+    let contents: [&str; 25] = std::array::from_fn(|ix| inputs[ix].as_str());
 
     drop(io_span);
 
-    macro_rules! day {
-        ($mod:ident, $day:expr) => {
-            if cli_day.unwrap_or($day) == $day {
-                let day = $day;
-                let (part1, part2) = aoc2022::$mod::$mod(contents[day - 1]);
-                write!(report, "{day}: {part1} {part2}\n").unwrap();
+    if options.measure_capacity {
+        let mut capacities = Vec::with_capacity(aoc2022::SOLUTIONS.len());
+        let mut failed = false;
+
+        for solution in aoc2022::SOLUTIONS {
+            let day = solution.day();
+            let parts = options.days.parts(day);
+
+            if !parts.is_empty() {
+                let input = contents[day as usize - 1];
+
+                match runner::capacity::measure(*solution, input, parts, config.params(day)) {
+                    Ok(capacity) => capacities.push((day, capacity)),
+                    Err(err) => {
+                        eprintln!("Error: {err}");
+                        failed = true;
+                    }
+                }
             }
-        };
-        ($mod:ident, $day:expr, $bump:expr) => {
-            if cli_day.unwrap_or($day) == $day {
-                $bump.reset();
-                let day = $day;
-                let (part1, part2) = aoc2022::$mod::$mod(&$bump, contents[day - 1]);
-                write!(report, "{day}: {part1} {part2}\n").unwrap();
+        }
+
+        runner::capacity::print_table(&capacities, capacity);
+
+        if failed {
+            return ExitCode::FAILURE;
+        }
+
+        return ExitCode::SUCCESS;
+    }
+
+    if let Some(bench_options) = &options.bench {
+        let mut bump = new_arena();
+        let mut samples = Vec::with_capacity(bench_options.iterations);
+        let mut stats = Vec::with_capacity(aoc2022::SOLUTIONS.len());
+        let mut errors = Vec::with_capacity(aoc2022::SOLUTIONS.len());
+
+        assert_no_alloc(|| {
+            for solution in aoc2022::SOLUTIONS {
+                let day = solution.day();
+
+                let parts = options.days.parts(day);
+
+                if !parts.is_empty() {
+                    let input = contents[day as usize - 1];
+                    match bench(
+                        *solution,
+                        &mut bump,
+                        input,
+                        parts,
+                        config.params(day),
+                        bench_options,
+                        &mut samples,
+                    ) {
+                        Ok(day_stats) => stats.push((day, day_stats)),
+                        Err(err) => errors.push(err),
+                    }
+                }
             }
-        };
+        });
+
+        print_table(&stats);
+
+        let rev = runner::history::git_revision();
+        let records = stats
+            .iter()
+            .map(|&(day, stats)| runner::history::Record {
+                rev: rev.clone(),
+                input_hash: runner::history::hash_input(contents[day as usize - 1]),
+                day,
+                parts: options.days.parts(day),
+                stats,
+            })
+            .collect::<Vec<_>>();
+
+        if let Err(err) = runner::history::append(options.history_path(), &records) {
+            eprintln!(
+                "Warn: {err} appending to {}",
+                options.history_path().display()
+            );
+        }
+
+        for err in errors.iter() {
+            eprintln!("Error: {err}");
+        }
+
+        if !errors.is_empty() {
+            return ExitCode::FAILURE;
+        }
+
+        return ExitCode::SUCCESS;
     }
 
-    assert_no_alloc(|| {
-        day!(day01, 1);
-        day!(day02, 2);
-        day!(day03, 3);
-        day!(day04, 4);
-        day!(day05, 5, bump);
-        day!(day06, 6);
-        day!(day07, 7, bump);
-        day!(day08, 8, bump);
-        day!(day09, 9, bump);
-        day!(day10, 10, bump);
-        day!(day11, 11, bump);
-        day!(day12, 12, bump);
-        day!(day13, 13, bump);
-        day!(day14, 14);
-        day!(day15, 15, bump);
-        day!(day18, 18, bump);
-        day!(day24, 24, bump);
-        day!(day25, 25, bump);
-    });
+    let mut report = Report::with_capacity(1024);
+
+    let jobs = aoc2022::SOLUTIONS
+        .iter()
+        .map(|&solution| (solution, options.days.parts(solution.day())))
+        .filter(|(_, parts)| !parts.is_empty())
+        .map(|(solution, parts)| {
+            let day = solution.day();
+            (
+                solution,
+                parts,
+                contents[day as usize - 1],
+                config.params(day),
+            )
+        })
+        .collect::<Vec<_>>();
+    let threads = options.jobs.map_or(1, NonZeroUsize::get);
+
+    let start = Instant::now();
+    runner::jobs::run(&jobs, threads, new_arena, &mut report);
+    let wall_time = start.elapsed();
 
     let io_span = tracing::span!(tracing::Level::TRACE, "Report");
     let _enter = io_span.enter();
 
-    print!("{report}");
+    eprintln!(
+        "Time: {:.1?} summed over days, {wall_time:.1?} wall-clock on {threads} thread(s)",
+        report.total_time()
+    );
 
-    Ok(())
-}
+    // Resetting an arena counts its chunk's footer as allocated, so measure a
+    // reset one to compare with the days' arenas
+    let mut arena = new_arena();
+    arena.reset();
+    let arena_capacity = arena.allocated_bytes();
 
-// This purposefully leaks strings
-// We should be able to hold all 25 days in memory quite easily
-fn get_content_for_day(path: impl AsRef<Path>) -> &'static str {
-    match std::fs::read_to_string(&path) {
-        Ok(content) => Box::leak(content.into_boxed_str()),
-        Err(err) => {
-            eprintln!("Warn: {err} on path {}", path.as_ref().display());
-            ""
+    eprintln!(
+        "Arena: up to {} bytes still used when a day returned, for a capacity of {arena_capacity} bytes",
+        report.arena_retained()
+    );
+
+    let mut global_allocs = false;
+
+    for result in report.days() {
+        if let Some(allocs) = result.allocs.filter(|allocs| allocs.count > 0) {
+            eprintln!(
+                "Warn: day {} made {} global allocation(s) of {} bytes, {} bytes at peak",
+                result.day, allocs.count, allocs.bytes, allocs.peak
+            );
+            global_allocs = true;
+        }
+
+        if result.arena.allocated > arena_capacity {
+            eprintln!(
+                "Warn: day {} outgrew its arena ({} > {arena_capacity} bytes), see --measure-capacity",
+                result.day, result.arena.allocated
+            );
+        }
+    }
+
+    if options.check {
+        if !check(&report, input_path) {
+            return ExitCode::FAILURE;
+        }
+    } else {
+        print!("{}", report.formatted(options.format));
+
+        for err in report.errors() {
+            eprintln!("Error: {err}");
+        }
+
+        if report.errors().next().is_some() {
+            return ExitCode::FAILURE;
         }
     }
+
+    if options.strict_alloc && global_allocs {
+        eprintln!("Error: global allocations were made with --strict-alloc");
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,358 @@
+//! Combinators shared by the days, on top of the ones nom provides
+
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+use core::ops::{Range, RangeFrom, RangeTo};
+
+use nom::character::complete::line_ending;
+use nom::error::{ErrorKind, ParseError};
+use nom::{AsChar, Compare, Err, IResult, InputIter, InputLength, InputTake, Parser, Slice};
+
+/// Like `nom::multi::separated_list0` but folding the elements instead of
+/// collecting them into a `Vec`
+pub fn separated_fold_many0<I, O, O2, E, F, G, H, S, R>(
+    mut sep: S,
+    mut f: F,
+    mut init: H,
+    mut g: G,
+) -> impl FnMut(I) -> IResult<I, R, E>
+where
+    I: Clone + InputLength,
+    F: Parser<I, O, E>,
+    S: Parser<I, O2, E>,
+    G: FnMut(R, O) -> R,
+    H: FnMut() -> R,
+    E: ParseError<I>,
+{
+    move |input: I| {
+        let mut res = init();
+
+        let mut input = match f.parse(input.clone()) {
+            Err(Err::Error(_)) => return Ok((input, res)),
+            Err(e) => return Err(e),
+            Ok((i, o)) => {
+                res = g(res, o);
+                i
+            }
+        };
+
+        loop {
+            let len = input.input_len();
+
+            match sep.parse(input.clone()) {
+                Err(Err::Error(_)) => return Ok((input, res)),
+                Err(e) => return Err(e),
+                Ok((i1, _)) => {
+                    // infinite loop check: the parser must always consume
+                    if i1.input_len() == len {
+                        return Err(Err::Error(E::from_error_kind(i1, ErrorKind::SeparatedList)));
+                    }
+
+                    match f.parse(i1.clone()) {
+                        Err(Err::Error(_)) => return Ok((input, res)),
+                        Err(e) => return Err(e),
+                        Ok((i2, o)) => {
+                            res = g(res, o);
+                            input = i2;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Fold the lines of the whole input, each parsed by `f`
+///
+/// Lines end with `\n` or `\r\n`, the last one possibly with neither. Like
+/// [`crate::parse::fold_all`], the first line which doesn't parse is an error.
+pub fn fold_lines<I, O, E, F, G, H, R>(
+    mut f: F,
+    mut init: H,
+    mut g: G,
+) -> impl FnMut(I) -> IResult<I, R, E>
+where
+    I: Clone + InputLength + InputIter + Compare<&'static str>,
+    I: Slice<Range<usize>> + Slice<RangeFrom<usize>> + Slice<RangeTo<usize>>,
+    F: Parser<I, O, E>,
+    G: FnMut(R, O) -> R,
+    H: FnMut() -> R,
+    E: ParseError<I>,
+{
+    move |mut input: I| {
+        let mut res = init();
+
+        while input.input_len() > 0 {
+            let (rest, o) = f.parse(input)?;
+
+            input = match rest.input_len() {
+                0 => rest,
+                _ => line_ending(rest)?.0,
+            };
+
+            res = g(res, o);
+        }
+
+        Ok((input, res))
+    }
+}
+
+/// Integers parsed from their decimal representation, with a sign for the
+/// signed ones
+pub trait Number: Sized + Copy + Default {
+    fn parse<I, E>(input: I) -> IResult<I, Self, E>
+    where
+        I: Clone + InputIter + InputLength + InputTake + Slice<RangeFrom<usize>>,
+        I: for<'a> Compare<&'a [u8]>,
+        <I as InputIter>::Item: AsChar,
+        E: ParseError<I>;
+}
+
+macro_rules! impl_number {
+    ($($t:ident),*) => {
+        $(
+            impl Number for $t {
+                fn parse<I, E>(input: I) -> IResult<I, Self, E>
+                where
+                    I: Clone + InputIter + InputLength + InputTake + Slice<RangeFrom<usize>>,
+                    I: for<'a> Compare<&'a [u8]>,
+                    <I as InputIter>::Item: AsChar,
+                    E: ParseError<I>,
+                {
+                    nom::character::complete::$t(input)
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+/// Numbers separated by `sep`, possibly none, collected in `alloc`
+pub fn number_list_in<I, T, O, E, S, A>(
+    alloc: A,
+    sep: S,
+) -> impl FnMut(I) -> IResult<I, Vec<T, A>, E>
+where
+    I: Clone + InputIter + InputLength + InputTake + Slice<RangeFrom<usize>>,
+    I: for<'a> Compare<&'a [u8]>,
+    <I as InputIter>::Item: AsChar,
+    T: Number,
+    S: Parser<I, O, E>,
+    E: ParseError<I>,
+    A: Allocator + Copy,
+{
+    separated_fold_many0(
+        sep,
+        T::parse,
+        move || Vec::new_in(alloc),
+        |mut numbers, n| {
+            numbers.push(n);
+            numbers
+        },
+    )
+}
+
+/// Exactly `N` numbers separated by `sep`, such as the `x,y,z` of a point
+pub fn number_array<I, T, O, E, S, const N: usize>(
+    mut sep: S,
+) -> impl FnMut(I) -> IResult<I, [T; N], E>
+where
+    I: Clone + InputIter + InputLength + InputTake + Slice<RangeFrom<usize>>,
+    I: for<'a> Compare<&'a [u8]>,
+    <I as InputIter>::Item: AsChar,
+    T: Number,
+    S: Parser<I, O, E>,
+    E: ParseError<I>,
+{
+    move |mut input: I| {
+        let mut numbers = [T::default(); N];
+
+        for (i, n) in numbers.iter_mut().enumerate() {
+            if i > 0 {
+                input = sep.parse(input)?.0;
+            }
+
+            (input, *n) = T::parse(input)?;
+        }
+
+        Ok((input, numbers))
+    }
+}
+
+/// A grid of cells, each parsed by `cell`, in lines of the same width
+///
+/// Returns the cells row after row, collected in `alloc`, and the width. Lines
+/// end with `\n` or `\r\n`, the last one possibly with neither.
+/// `cell` mustn't accept the line endings.
+pub fn grid_in<I, T, E, F, A>(
+    alloc: A,
+    mut cell: F,
+) -> impl FnMut(I) -> IResult<I, (Vec<T, A>, usize), E>
+where
+    I: Clone + InputLength + InputIter + Compare<&'static str>,
+    I: Slice<Range<usize>> + Slice<RangeFrom<usize>> + Slice<RangeTo<usize>>,
+    F: Parser<I, T, E>,
+    E: ParseError<I>,
+    A: Allocator + Copy,
+{
+    move |mut input: I| {
+        let mut cells = Vec::new_in(alloc);
+        let mut width = None;
+
+        while input.input_len() > 0 {
+            let mut row_width = 0;
+
+            while width != Some(row_width) {
+                let len = input.input_len();
+
+                match cell.parse(input.clone()) {
+                    Ok((rest, c)) => {
+                        // infinite loop check: the parser must always consume
+                        if rest.input_len() == len {
+                            return Err(Err::Error(E::from_error_kind(rest, ErrorKind::Many1)));
+                        }
+
+                        cells.push(c);
+                        row_width += 1;
+                        input = rest;
+                    }
+                    // The first row sets the width where its cells end
+                    Err(Err::Error(_))
+                        if width.is_none()
+                            && (len == 0 || line_ending::<I, E>(input.clone()).is_ok()) =>
+                    {
+                        break
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+
+            if width.is_none() {
+                width = Some(row_width);
+                // Assuming a cell per item, the rows left fit without growing
+                cells.reserve(row_width * input.input_len() / (row_width + 1));
+            }
+
+            if input.input_len() > 0 {
+                input = line_ending(input)?.0;
+            }
+        }
+
+        Ok((input, (cells, width.unwrap_or(0))))
+    }
+}
+
+#[cfg(test)]
+use crate::parse::{Error, Expected};
+
+#[test]
+fn fold_lines_endings() {
+    use nom::character::complete::u32;
+
+    let sum = |input| fold_lines(u32::<_, Error<_>>, || 0, |sum, n| sum + n)(input);
+
+    assert_eq!(sum("1\n2\r\n3\n"), Ok(("", 6)));
+    assert_eq!(sum("1\r\n2"), Ok(("", 3)));
+    assert_eq!(sum(""), Ok(("", 0)));
+    assert_eq!(
+        sum("1\n2x\n"),
+        Err(Err::Error(Error {
+            input: "x\n",
+            expected: Expected::Kind(ErrorKind::CrLf),
+        }))
+    );
+    assert_eq!(
+        sum("1\n\n"),
+        Err(Err::Error(Error {
+            input: "\n",
+            expected: Expected::Kind(ErrorKind::Digit),
+        }))
+    );
+}
+
+#[test]
+fn number_lists() {
+    use nom::bytes::complete::tag;
+    use nom::character::complete::char;
+
+    let bump = bumpalo::Bump::new();
+
+    let (rest, numbers) =
+        number_list_in::<_, u64, _, Error<_>, _, _>(&bump, tag(", "))("79, 98\n").unwrap();
+    assert_eq!((rest, &numbers[..]), ("\n", &[79, 98][..]));
+
+    let (rest, numbers) =
+        number_list_in::<_, i32, _, Error<_>, _, _>(&bump, char(' '))("-1 2 -3").unwrap();
+    assert_eq!((rest, &numbers[..]), ("", &[-1, 2, -3][..]));
+
+    let (rest, numbers) =
+        number_list_in::<_, u8, _, Error<_>, _, _>(&bump, char(','))("x").unwrap();
+    assert_eq!((rest, numbers.len()), ("x", 0));
+
+    assert_eq!(
+        number_array::<_, u8, _, Error<_>, _, 3>(char(','))(&b"2,12,3\n"[..]),
+        Ok((&b"\n"[..], [2, 12, 3]))
+    );
+    assert_eq!(
+        number_array::<_, i16, _, Error<_>, _, 2>(char(','))("-5,7"),
+        Ok(("", [-5, 7]))
+    );
+    assert_eq!(
+        number_array::<_, u8, _, Error<_>, _, 3>(char(','))("2,12"),
+        Err(Err::Error(Error {
+            input: "",
+            expected: Expected::Char(','),
+        }))
+    );
+}
+
+#[test]
+fn grids() {
+    use nom::character::complete::one_of;
+
+    let bump = bumpalo::Bump::new();
+    let mut grid = grid_in::<_, _, Error<_>, _, _>(&bump, one_of("#."));
+
+    let (rest, (cells, width)) = grid("#.#\r\n..#\n").unwrap();
+    assert_eq!(rest, "");
+    assert_eq!(width, 3);
+    assert_eq!(&cells[..], &['#', '.', '#', '.', '.', '#']);
+
+    let (_, (cells, width)) = grid("#.\n.#").unwrap();
+    assert_eq!((cells.len(), width), (4, 2));
+
+    // Rows of different widths
+    assert_eq!(
+        grid("#.#\n.#\n"),
+        Err(Err::Error(Error {
+            input: "\n",
+            expected: Expected::Kind(ErrorKind::OneOf),
+        }))
+    );
+    assert_eq!(
+        grid("#.\n.##\n"),
+        Err(Err::Error(Error {
+            input: "#\n",
+            expected: Expected::Kind(ErrorKind::CrLf),
+        }))
+    );
+    assert_eq!(
+        grid("#x\n"),
+        Err(Err::Error(Error {
+            input: "x\n",
+            expected: Expected::Kind(ErrorKind::OneOf),
+        }))
+    );
+}
+
+#[test]
+fn separated_fold() {
+    use nom::character::complete::{char, u32};
+
+    let mut sum = separated_fold_many0(char(','), u32::<_, Error<_>>, || 0, |sum, n| sum + n);
+
+    assert_eq!(sum("1,2,3]"), Ok(("]", 6)));
+    assert_eq!(sum("]"), Ok(("]", 0)));
+    assert_eq!(sum("1,]"), Ok((",]", 1)));
+}
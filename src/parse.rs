@@ -0,0 +1,280 @@
+use core::fmt::{self, Display};
+
+use nom::error::ErrorKind;
+use nom::{AsBytes, InputLength, Parser};
+
+use crate::Param;
+
+/// Why a day's input couldn't be parsed, and where, or which of its
+/// parameters was out of range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u8,
+    /// Line of the input, starting at 1, 0 for a parameter
+    pub line: usize,
+    /// Column in bytes, starting at 1
+    pub column: usize,
+    pub expected: Expected,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expected {
+    Char(char),
+    Token(&'static str),
+    Kind(ErrorKind),
+    /// A value of the parameter within those it accepts
+    Param(&'static Param),
+}
+
+impl ParseError {
+    /// Locate an error at `at`, which has to point within `input`
+    pub(crate) fn at(day: u8, input: &[u8], at: &[u8], expected: Expected) -> Self {
+        let offset = (at.as_ptr() as usize)
+            .saturating_sub(input.as_ptr() as usize)
+            .min(input.len());
+        let before = &input[..offset];
+
+        let line = 1 + before.iter().filter(|&&c| c == b'\n').count();
+        let line_start = before
+            .iter()
+            .rposition(|&c| c == b'\n')
+            .map_or(0, |ix| ix + 1);
+
+        ParseError {
+            day,
+            line,
+            column: 1 + offset - line_start,
+            expected,
+        }
+    }
+
+    /// A value of `param` which isn't one it accepts
+    pub fn param(day: u8, param: &'static Param) -> Self {
+        ParseError {
+            day,
+            line: 0,
+            column: 0,
+            expected: Expected::Param(param),
+        }
+    }
+
+    /// Locate the error of a parser run on a part of `input`
+    pub(crate) fn from_nom<I: AsBytes>(day: u8, input: &[u8], err: nom::Err<Error<I>>) -> Self {
+        match err {
+            nom::Err::Error(e) | nom::Err::Failure(e) => {
+                ParseError::at(day, input, e.input.as_bytes(), e.expected)
+            }
+            nom::Err::Incomplete(_) => ParseError::at(
+                day,
+                input,
+                &input[input.len()..],
+                Expected::Kind(ErrorKind::Eof),
+            ),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.expected {
+            Expected::Param(_) => write!(f, "day {}: expected {}", self.day, self.expected),
+            _ => write!(
+                f,
+                "day {}, line {}, column {}: expected {}",
+                self.day, self.line, self.column, self.expected
+            ),
+        }
+    }
+}
+
+impl Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Char(c) => write!(f, "{c:?}"),
+            Expected::Token(token) => f.write_str(token),
+            Expected::Param(param) => write!(
+                f,
+                "{} within {}..={}",
+                param.name,
+                param.values.start(),
+                param.values.end()
+            ),
+            Expected::Kind(kind) => f.write_str(match kind {
+                ErrorKind::Digit => "a number",
+                ErrorKind::Alpha => "a letter",
+                ErrorKind::Tag => "a keyword",
+                ErrorKind::CrLf => "a line ending",
+                ErrorKind::Eof => "more input",
+                ErrorKind::TakeUntil => "a terminator",
+                ErrorKind::Many1 => "at least one item",
+                kind => kind.description(),
+            }),
+        }
+    }
+}
+
+/// Error type for the nom parsers of each day, keeping what was expected
+#[derive(Debug, PartialEq)]
+pub(crate) struct Error<I> {
+    pub input: I,
+    pub expected: Expected,
+}
+
+pub(crate) type IResult<I, O> = nom::IResult<I, O, Error<I>>;
+
+impl<I: InputLength> nom::error::ParseError<I> for Error<I> {
+    fn from_error_kind(input: I, kind: ErrorKind) -> Self {
+        let expected = match kind {
+            ErrorKind::Eof if input.input_len() > 0 => Expected::Token("end of input"),
+            kind => Expected::Kind(kind),
+        };
+
+        Error { input, expected }
+    }
+
+    fn append(_input: I, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: I, c: char) -> Self {
+        Error {
+            input,
+            expected: Expected::Char(c),
+        }
+    }
+
+    // Keep the alternative which went the furthest
+    fn or(self, other: Self) -> Self {
+        if other.input.input_len() <= self.input.input_len() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+/// Like `nom::multi::fold_many0`, but the whole input has to be consumed
+///
+/// Instead of stopping silently at the first element which doesn't parse, this
+/// reports its error
+pub(crate) fn fold_all<I, O, F, G, H, R>(
+    mut f: F,
+    mut init: H,
+    mut g: G,
+) -> impl FnMut(I) -> IResult<I, R>
+where
+    I: Clone + InputLength,
+    F: Parser<I, O, Error<I>>,
+    G: FnMut(R, O) -> R,
+    H: FnMut() -> R,
+{
+    move |mut input: I| {
+        let mut res = init();
+
+        while input.input_len() > 0 {
+            let len = input.input_len();
+            let (rest, o) = f.parse(input)?;
+
+            // infinite loop check: the parser must always consume
+            if rest.input_len() == len {
+                return Err(nom::Err::Error(nom::error::ParseError::from_error_kind(
+                    rest,
+                    ErrorKind::Many0,
+                )));
+            }
+
+            res = g(res, o);
+            input = rest;
+        }
+
+        Ok((input, res))
+    }
+}
+
+/// One byte turned into a value by `f`, which rejects the bytes other than
+/// `expected` with `None`
+pub(crate) fn byte<T>(
+    expected: &'static str,
+    f: impl Fn(u8) -> Option<T>,
+) -> impl Fn(&[u8]) -> IResult<&[u8], T> {
+    move |input| match input.first().and_then(|&c| f(c)) {
+        Some(value) => Ok((&input[1..], value)),
+        None => Err(nom::Err::Error(Error {
+            input,
+            expected: Expected::Token(expected),
+        })),
+    }
+}
+
+/// Run a parser over the whole input of a day
+pub(crate) fn parse_all<I, O>(
+    day: u8,
+    input: I,
+    mut parser: impl Parser<I, O, Error<I>>,
+) -> Result<O, ParseError>
+where
+    I: AsBytes + InputLength + Clone,
+{
+    match parser.parse(input.clone()) {
+        Ok((rest, o)) if rest.input_len() == 0 => Ok(o),
+        Ok((rest, _)) => Err(ParseError::at(
+            day,
+            input.as_bytes(),
+            rest.as_bytes(),
+            Expected::Token("end of input"),
+        )),
+        Err(err) => Err(ParseError::from_nom(day, input.as_bytes(), err)),
+    }
+}
+
+#[test]
+fn error_location() {
+    use nom::character::complete::{char, u32};
+    use nom::sequence::terminated;
+
+    let input = "1\n2\n3x\n4\n";
+    let err = parse_all(
+        42,
+        input,
+        fold_all(terminated(u32, char('\n')), || 0, |a, b| a + b),
+    );
+
+    assert_eq!(
+        err,
+        Err(ParseError {
+            day: 42,
+            line: 3,
+            column: 2,
+            expected: Expected::Char('\n'),
+        })
+    );
+    assert_eq!(
+        err.unwrap_err().to_string(),
+        r"day 42, line 3, column 2: expected '\n'"
+    );
+
+    assert_eq!(
+        parse_all(
+            42,
+            input,
+            fold_all(terminated(u32, char('\n')), || 0, |a, b| a + b)
+        ),
+        parse_all(
+            42,
+            input.as_bytes(),
+            fold_all(terminated(u32, char('\n')), || 0, |a, b| a + b)
+        )
+    );
+
+    assert_eq!(
+        parse_all(
+            1,
+            "1\n2\n",
+            fold_all(terminated(u32, char('\n')), || 0, |a, b| a + b)
+        ),
+        Ok(3)
+    );
+}
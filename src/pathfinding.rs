@@ -0,0 +1,557 @@
+//! Shortest paths in graphs, with the storage of the searches left to the
+//! caller
+//!
+//! A search keeps the cost of the nodes reached in a [`Visited`], the nodes to
+//! expand in a [`Frontier`], and how each node was reached in a [`Parents`]
+//! when the path matters and not only its cost.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+use core::hash::{BuildHasher, Hash};
+
+use hashbrown::hash_map::Entry;
+use hashbrown::HashMap;
+use num::{one, zero, PrimInt, Unsigned};
+
+use crate::grid::Grid;
+
+pub trait Graph {
+    type Node: Copy;
+    type Cost: PrimInt + Unsigned;
+
+    /// Call `visit` with each node reachable in one step from `node`, and the
+    /// cost of that step
+    fn successors(&self, node: Self::Node, visit: impl FnMut(Self::Node, Self::Cost));
+
+    /// Lower bound of the cost from `node` to the closest goal, used by
+    /// [`a_star`]
+    fn heuristic(&self, _node: Self::Node) -> Self::Cost {
+        zero()
+    }
+}
+
+/// Lowest cost at which each node was reached so far
+pub trait Visited<N, C> {
+    /// Record reaching `node` at `cost`, unless it was already reached as
+    /// cheaply, returning whether it was recorded
+    fn improve(&mut self, node: N, cost: C) -> bool;
+}
+
+impl<N: Eq + Hash, C: Ord, S: BuildHasher, A: Allocator + Clone> Visited<N, C>
+    for HashMap<N, C, S, A>
+{
+    fn improve(&mut self, node: N, cost: C) -> bool {
+        match self.entry(node) {
+            Entry::Occupied(mut entry) if cost < *entry.get() => {
+                entry.insert(cost);
+                true
+            }
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(cost);
+                true
+            }
+        }
+    }
+}
+
+/// For positions in a grid, the cells of those not reached yet at the cost's
+/// maximum value
+impl<C: PrimInt, A: Allocator> Visited<(usize, usize), C> for Grid<C, A> {
+    fn improve(&mut self, pos: (usize, usize), cost: C) -> bool {
+        let best = &mut self[pos];
+
+        if cost < *best {
+            *best = cost;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Nodes left to expand, taken in order of priority
+pub trait Frontier<C, T> {
+    fn push(&mut self, priority: C, item: T);
+    fn pop(&mut self) -> Option<T>;
+}
+
+/// First in, first out, which is in order of priority when it never decreases,
+/// as in a breadth-first search
+impl<C, T, A: Allocator> Frontier<C, T> for VecDeque<T, A> {
+    fn push(&mut self, _priority: C, item: T) {
+        self.push_back(item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+}
+
+/// Binary heap taking the lowest priority first, and of those the lowest item
+#[derive(Debug, Clone)]
+pub struct MinHeap<C, T, A: Allocator> {
+    items: Vec<(C, T), A>,
+}
+
+impl<C: Ord, T: Ord, A: Allocator> MinHeap<C, T, A> {
+    pub fn new_in(alloc: A) -> Self {
+        MinHeap {
+            items: Vec::new_in(alloc),
+        }
+    }
+
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        MinHeap {
+            items: Vec::with_capacity_in(capacity, alloc),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl<C: Ord, T: Ord, A: Allocator> Frontier<C, T> for MinHeap<C, T, A> {
+    fn push(&mut self, priority: C, item: T) {
+        let items = &mut self.items;
+        let mut i = items.len();
+        items.push((priority, item));
+
+        while i > 0 {
+            let parent = (i - 1) / 2;
+
+            if items[parent] <= items[i] {
+                break;
+            }
+
+            items.swap(parent, i);
+            i = parent;
+        }
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let items = &mut self.items;
+        let last = items.len().checked_sub(1)?;
+        items.swap(0, last);
+        let (_, item) = items.pop()?;
+
+        let mut i = 0;
+
+        loop {
+            let left = 2 * i + 1;
+            let right = left + 1;
+            let mut lowest = i;
+
+            if left < items.len() && items[left] < items[lowest] {
+                lowest = left;
+            }
+            if right < items.len() && items[right] < items[lowest] {
+                lowest = right;
+            }
+            if lowest == i {
+                break;
+            }
+
+            items.swap(i, lowest);
+            i = lowest;
+        }
+
+        Some(item)
+    }
+}
+
+/// Buckets of the items by priority, taken in the order they were pushed, for
+/// priorities which never go below the lowest one left and stay within `span`
+/// of it
+///
+/// That's the case of Dijkstra's algorithm when the steps cost less than
+/// `span`, and of A* when the step cost plus the change of the heuristic does.
+/// The buckets are used in turn, so the memory is bounded by the items left.
+#[derive(Debug, Clone)]
+pub struct BucketQueue<T, A: Allocator> {
+    buckets: Vec<VecDeque<T, A>, A>,
+    /// No item has a lower priority
+    lowest: usize,
+    len: usize,
+}
+
+impl<T, A: Allocator + Clone> BucketQueue<T, A> {
+    pub fn new_in(span: usize, alloc: A) -> Self {
+        let mut buckets = Vec::with_capacity_in(span, alloc.clone());
+        buckets.resize_with(span, || VecDeque::new_in(alloc.clone()));
+
+        BucketQueue {
+            buckets,
+            lowest: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<C: PrimInt, T, A: Allocator> Frontier<C, T> for BucketQueue<T, A> {
+    fn push(&mut self, priority: C, item: T) {
+        let priority = priority.to_usize().unwrap();
+        let span = self.buckets.len();
+
+        // Once empty, starting over from any priority
+        if self.len == 0 && !(self.lowest..self.lowest + span).contains(&priority) {
+            self.lowest = priority;
+        }
+
+        assert!(
+            (self.lowest..self.lowest + span).contains(&priority),
+            "priority {priority} out of the span of {span} from {}",
+            self.lowest
+        );
+
+        self.buckets[priority % span].push_back(item);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let span = self.buckets.len();
+
+        loop {
+            if let Some(item) = self.buckets[self.lowest % span].pop_front() {
+                self.len -= 1;
+                return Some(item);
+            }
+
+            self.lowest += 1;
+        }
+    }
+}
+
+/// How each node was first reached, when a search needs to find its path
+pub trait Parents<N> {
+    fn insert(&mut self, node: N, parent: N);
+}
+
+/// Keep only the costs, not the paths
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoPath;
+
+impl<N> Parents<N> for NoPath {
+    fn insert(&mut self, _node: N, _parent: N) {}
+}
+
+/// The parent of each node reached, leading back to the start
+#[derive(Debug, Clone)]
+pub struct PathTree<N, S, A: Allocator + Clone> {
+    parents: HashMap<N, N, S, A>,
+}
+
+impl<N: Copy + Eq + Hash, S: BuildHasher + Default, A: Allocator + Clone> PathTree<N, S, A> {
+    pub fn new_in(alloc: A) -> Self {
+        PathTree {
+            parents: HashMap::with_hasher_in(S::default(), alloc),
+        }
+    }
+
+    /// The nodes from the start of the search to `node`, both included
+    pub fn path_to(&self, node: N) -> Vec<N, A> {
+        let mut path = Vec::new_in(self.parents.allocator().clone());
+        path.push(node);
+
+        while let Some(&parent) = self.parents.get(path.last().unwrap()) {
+            path.push(parent);
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+impl<N: Eq + Hash, S: BuildHasher, A: Allocator + Clone> Parents<N> for PathTree<N, S, A> {
+    fn insert(&mut self, node: N, parent: N) {
+        self.parents.insert(node, parent);
+    }
+}
+
+/// Fewest steps from `start` to a node accepted by `is_goal`, whatever the
+/// cost of each step, with the goal reached
+///
+/// The frontier can be a `VecDeque`, its order being the one of the steps.
+pub fn bfs<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    is_goal: impl FnMut(G::Node) -> bool,
+    visited: &mut impl Visited<G::Node, G::Cost>,
+    frontier: &mut impl Frontier<G::Cost, (G::Node, G::Cost)>,
+    parents: &mut impl Parents<G::Node>,
+) -> Option<(G::Cost, G::Node)> {
+    search(
+        graph,
+        start,
+        is_goal,
+        (visited, frontier, parents),
+        |_| one(),
+        |_| zero(),
+    )
+}
+
+/// Lowest cost from `start` to a node accepted by `is_goal`, with the goal
+/// reached
+pub fn dijkstra<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    is_goal: impl FnMut(G::Node) -> bool,
+    visited: &mut impl Visited<G::Node, G::Cost>,
+    frontier: &mut impl Frontier<G::Cost, (G::Node, G::Cost)>,
+    parents: &mut impl Parents<G::Node>,
+) -> Option<(G::Cost, G::Node)> {
+    search(
+        graph,
+        start,
+        is_goal,
+        (visited, frontier, parents),
+        |cost| cost,
+        |_| zero(),
+    )
+}
+
+/// Like [`dijkstra`], expanding first the nodes closest to a goal according
+/// to the graph's heuristic, which must never overestimate
+pub fn a_star<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    is_goal: impl FnMut(G::Node) -> bool,
+    visited: &mut impl Visited<G::Node, G::Cost>,
+    frontier: &mut impl Frontier<G::Cost, (G::Node, G::Cost)>,
+    parents: &mut impl Parents<G::Node>,
+) -> Option<(G::Cost, G::Node)> {
+    search(
+        graph,
+        start,
+        is_goal,
+        (visited, frontier, parents),
+        |cost| cost,
+        |node| graph.heuristic(node),
+    )
+}
+
+fn search<G: Graph, V, F, P>(
+    graph: &G,
+    start: G::Node,
+    mut is_goal: impl FnMut(G::Node) -> bool,
+    (visited, frontier, parents): (&mut V, &mut F, &mut P),
+    step_cost: impl Fn(G::Cost) -> G::Cost,
+    heuristic: impl Fn(G::Node) -> G::Cost,
+) -> Option<(G::Cost, G::Node)>
+where
+    V: Visited<G::Node, G::Cost>,
+    F: Frontier<G::Cost, (G::Node, G::Cost)>,
+    P: Parents<G::Node>,
+{
+    visited.improve(start, zero());
+    frontier.push(heuristic(start), (start, zero()));
+
+    // Nodes reached again more cheaply are expanded again, but without
+    // effect as their successors were reached from the cheaper one
+    while let Some((node, cost)) = frontier.pop() {
+        if is_goal(node) {
+            return Some((cost, node));
+        }
+
+        graph.successors(node, |next, step| {
+            let next_cost = cost + step_cost(step);
+
+            if visited.improve(next, next_cost) {
+                parents.insert(next, node);
+                frontier.push(next_cost + heuristic(next), (next, next_cost));
+            }
+        });
+    }
+
+    None
+}
+
+/// Weighted edges `(from, to, cost)` of a directed graph, and the estimate of
+/// each node to the goal
+#[cfg(test)]
+struct Edges(&'static [(u8, u8, u32)], &'static [u32]);
+
+#[cfg(test)]
+impl Graph for Edges {
+    type Node = u8;
+    type Cost = u32;
+
+    fn successors(&self, node: u8, mut visit: impl FnMut(u8, u32)) {
+        for &(from, to, cost) in self.0 {
+            if from == node {
+                visit(to, cost);
+            }
+        }
+    }
+
+    fn heuristic(&self, node: u8) -> u32 {
+        self.1[node as usize]
+    }
+}
+
+#[cfg(test)]
+const EDGES: Edges = Edges(
+    &[
+        (0, 1, 7),
+        (0, 2, 9),
+        (0, 5, 14),
+        (1, 2, 10),
+        (1, 3, 15),
+        (2, 3, 11),
+        (2, 5, 2),
+        (3, 4, 6),
+        (5, 4, 9),
+    ],
+    &[20, 15, 11, 6, 0, 9, 0],
+);
+
+#[cfg(test)]
+type Tree<'a> = PathTree<u8, crate::hash::DefaultHasherBuilder, &'a bumpalo::Bump>;
+
+#[test]
+fn shortest_paths() {
+    let bump = bumpalo::Bump::new();
+    let goal = |node| node == 4;
+
+    let mut tree = Tree::new_in(&bump);
+    let found = dijkstra(
+        &EDGES,
+        0,
+        goal,
+        &mut crate::hash_map!(&bump),
+        &mut MinHeap::new_in(&bump),
+        &mut tree,
+    );
+    assert_eq!(found, Some((20, 4)));
+    assert_eq!(tree.path_to(4), [0, 2, 5, 4]);
+
+    let mut tree = Tree::new_in(&bump);
+    let found = a_star(
+        &EDGES,
+        0,
+        goal,
+        &mut crate::hash_map!(&bump),
+        &mut BucketQueue::new_in(16, &bump),
+        &mut tree,
+    );
+    assert_eq!(found, Some((20, 4)));
+    assert_eq!(tree.path_to(4), [0, 2, 5, 4]);
+
+    let mut tree = Tree::new_in(&bump);
+    let found = bfs(
+        &EDGES,
+        0,
+        goal,
+        &mut crate::hash_map!(&bump),
+        &mut VecDeque::new_in(&bump),
+        &mut tree,
+    );
+    assert_eq!(found, Some((2, 4)));
+    assert_eq!(tree.path_to(4), [0, 5, 4]);
+
+    // Unreachable
+    let found = dijkstra(
+        &EDGES,
+        0,
+        |node| node == 6,
+        &mut crate::hash_map!(&bump),
+        &mut MinHeap::new_in(&bump),
+        &mut NoPath,
+    );
+    assert_eq!(found, None);
+}
+
+#[test]
+fn grid_maze() {
+    use nom::character::complete::one_of;
+
+    struct Maze<'a>(Grid<char, &'a bumpalo::Bump>);
+
+    impl Graph for Maze<'_> {
+        type Node = (usize, usize);
+        type Cost = u16;
+
+        fn successors(&self, (x, y): (usize, usize), mut visit: impl FnMut((usize, usize), u16)) {
+            for next in self.0.neighbours4(x, y) {
+                if self.0[next] == '.' {
+                    visit(next, 1);
+                }
+            }
+        }
+    }
+
+    let bump = bumpalo::Bump::new();
+    let (_, grid) = Grid::parser::<_, crate::parse::Error<_>, _>(&bump, one_of("#."))(
+        "..#..\n\
+         #.#..\n\
+         ...#.\n\
+         .#...\n",
+    )
+    .unwrap();
+    let maze = Maze(grid);
+
+    let mut costs = Grid::new_in(5, 4, u16::MAX, &bump);
+    let mut tree = PathTree::<_, crate::hash::DefaultHasherBuilder, _>::new_in(&bump);
+    let found = bfs(
+        &maze,
+        (0, 0),
+        |pos| pos == (3, 0),
+        &mut costs,
+        &mut VecDeque::new_in(&bump),
+        &mut tree,
+    );
+
+    assert_eq!(found, Some((11, (3, 0))));
+    assert_eq!(tree.path_to((3, 0)).len(), 12);
+    assert_eq!(costs[(0, 2)], 4);
+    assert_eq!(costs[(2, 0)], u16::MAX);
+}
+
+#[test]
+fn queues() {
+    let bump = bumpalo::Bump::new();
+
+    let mut heap = MinHeap::new_in(&bump);
+    for (priority, item) in [(5, 'a'), (1, 'b'), (3, 'c'), (1, 'd'), (4, 'e')] {
+        heap.push(priority, item);
+    }
+    assert_eq!(heap.len(), 5);
+    assert_eq!(
+        core::iter::from_fn(|| heap.pop()).collect::<Vec<_>>(),
+        ['b', 'd', 'c', 'e', 'a']
+    );
+
+    let mut buckets = BucketQueue::new_in(3, &bump);
+    buckets.push(10u32, 'a');
+    buckets.push(12, 'b');
+    buckets.push(11, 'c');
+    assert_eq!(Frontier::<u32, _>::pop(&mut buckets), Some('a'));
+    buckets.push(12, 'd');
+    assert_eq!(
+        core::iter::from_fn(|| Frontier::<u32, _>::pop(&mut buckets)).collect::<Vec<_>>(),
+        ['c', 'b', 'd']
+    );
+    assert!(buckets.is_empty());
+
+    // Starting over from any priority once empty
+    buckets.push(2, 'e');
+    assert_eq!(Frontier::<u32, _>::pop(&mut buckets), Some('e'));
+}
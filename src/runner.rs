@@ -0,0 +1,118 @@
+use bumpalo::Bump;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub mod alloc_count;
+pub mod bench;
+pub mod capacity;
+pub mod check;
+pub mod cli;
+pub mod config;
+pub mod example;
+#[cfg(feature = "trace")]
+pub mod folded;
+pub mod history;
+pub mod jobs;
+pub mod report;
+pub mod selection;
+#[cfg(feature = "trace")]
+pub mod span_summary;
+pub mod watch;
+
+pub const DEFAULT_INPUT_DIR: &str = "inputs";
+
+/// Path standing for the standard input instead of a file
+pub const STDIN_PATH: &str = "-";
+
+pub fn default_input_path(input_dir: &Path, day: u8) -> PathBuf {
+    input_dir.join(format!("day{day:0>2}.txt"))
+}
+
+/// How much of its arena a day used
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ArenaUsage {
+    /// Bytes still allocated in the arena once the day returned, which misses
+    /// what it freed before: `--measure-capacity` finds the peak
+    pub retained: usize,
+    /// Total size of the arena's chunks, which grows past its initial
+    /// capacity when a day doesn't fit in it
+    pub allocated: usize,
+}
+
+impl ArenaUsage {
+    pub fn of(bump: &Bump) -> ArenaUsage {
+        // SAFETY: the chunks' data isn't read, and the iterator is gone before
+        // the next allocation
+        let retained = unsafe { bump.iter_allocated_chunks_raw() }
+            .map(|(_, len)| len)
+            .sum();
+
+        ArenaUsage {
+            retained,
+            allocated: bump.allocated_bytes(),
+        }
+    }
+}
+
+/// The input of a day, kept alive for the whole run
+#[derive(Default)]
+pub enum Input {
+    #[default]
+    Empty,
+    /// Memory-mapped file, checked to be valid UTF-8
+    Mapped(Mmap),
+    /// Read from the standard input, or from a file which may change
+    Owned(String),
+}
+
+impl Input {
+    /// Map the file at `path`, checking once that it is valid UTF-8, or read
+    /// the standard input when `path` is [`STDIN_PATH`]
+    ///
+    /// An input which can't be read is treated as empty, with a warning
+    pub fn load(path: impl AsRef<Path>) -> Input {
+        let path = path.as_ref();
+
+        let input = if path == Path::new(STDIN_PATH) {
+            io::read_to_string(io::stdin()).map(Input::Owned)
+        } else {
+            File::open(path).and_then(|file| {
+                // SAFETY: inputs aren't expected to change while we're running
+                let mmap = unsafe { Mmap::map(&file)? };
+
+                match std::str::from_utf8(&mmap) {
+                    Ok(_) => Ok(Input::Mapped(mmap)),
+                    Err(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+                }
+            })
+        };
+
+        input.unwrap_or_else(|err| warn_empty(path, err))
+    }
+
+    /// Read the file at `path` into memory, for files which may change while
+    /// we're running
+    pub fn read(path: impl AsRef<Path>) -> Input {
+        let path = path.as_ref();
+
+        std::fs::read_to_string(path)
+            .map(Input::Owned)
+            .unwrap_or_else(|err| warn_empty(path, err))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Input::Empty => "",
+            // SAFETY: checked when loading
+            Input::Mapped(mmap) => unsafe { std::str::from_utf8_unchecked(mmap) },
+            Input::Owned(content) => content,
+        }
+    }
+}
+
+fn warn_empty(path: &Path, err: io::Error) -> Input {
+    eprintln!("Warn: {err} on path {}", path.display());
+    Input::Empty
+}
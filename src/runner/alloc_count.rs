@@ -0,0 +1,244 @@
+/// Global allocations made while a day ran
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct AllocStats {
+    /// Number of allocations and reallocations
+    pub count: usize,
+    /// Bytes requested by those
+    pub bytes: usize,
+    /// Most bytes held at once, not counting what was allocated before
+    pub peak: usize,
+}
+
+/// Run `f`, counting the global allocations it makes on this thread
+///
+/// Only counts with the `count-alloc` feature, which installs
+/// [`CountingAlloc`] as the global allocator
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<AllocStats>) {
+    #[cfg(feature = "count-alloc")]
+    {
+        counting::start();
+        let result = f();
+        (result, Some(counting::stop()))
+    }
+
+    #[cfg(not(feature = "count-alloc"))]
+    (f(), None)
+}
+
+/// Run `f` without counting its allocations against the day being measured
+#[cfg(any(feature = "trace", all(feature = "count-alloc", test)))]
+pub fn uncounted<T>(f: impl FnOnce() -> T) -> T {
+    #[cfg(feature = "count-alloc")]
+    {
+        let paused = counting::pause();
+        let result = f();
+        counting::resume(paused);
+        result
+    }
+
+    #[cfg(not(feature = "count-alloc"))]
+    f()
+}
+
+/// Subscriber whose spans and events aren't counted as allocations of the
+/// day they are in, since the layers allocate to record them
+#[cfg(feature = "trace")]
+pub struct Uncounted<S>(pub S);
+
+#[cfg(feature = "trace")]
+mod uncounted_subscriber {
+    use core::any::TypeId;
+    use tracing::level_filters::LevelFilter;
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::subscriber::Interest;
+    use tracing::{Event, Metadata, Subscriber};
+    use tracing_core::span::Current;
+
+    use super::{uncounted, Uncounted};
+
+    impl<S: Subscriber> Subscriber for Uncounted<S> {
+        fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+            uncounted(|| self.0.register_callsite(metadata))
+        }
+
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            uncounted(|| self.0.enabled(metadata))
+        }
+
+        fn max_level_hint(&self) -> Option<LevelFilter> {
+            self.0.max_level_hint()
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            uncounted(|| self.0.new_span(span))
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            uncounted(|| self.0.record(span, values))
+        }
+
+        fn record_follows_from(&self, span: &Id, follows: &Id) {
+            uncounted(|| self.0.record_follows_from(span, follows))
+        }
+
+        fn event_enabled(&self, event: &Event<'_>) -> bool {
+            uncounted(|| self.0.event_enabled(event))
+        }
+
+        fn event(&self, event: &Event<'_>) {
+            uncounted(|| self.0.event(event))
+        }
+
+        fn enter(&self, span: &Id) {
+            uncounted(|| self.0.enter(span))
+        }
+
+        fn exit(&self, span: &Id) {
+            uncounted(|| self.0.exit(span))
+        }
+
+        fn clone_span(&self, id: &Id) -> Id {
+            uncounted(|| self.0.clone_span(id))
+        }
+
+        fn try_close(&self, id: Id) -> bool {
+            uncounted(|| self.0.try_close(id))
+        }
+
+        fn current_span(&self) -> Current {
+            self.0.current_span()
+        }
+
+        unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+            if id == TypeId::of::<Self>() {
+                Some(self as *const Self as *const ())
+            } else {
+                self.0.downcast_raw(id)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "count-alloc")]
+pub use counting::CountingAlloc;
+
+#[cfg(feature = "count-alloc")]
+mod counting {
+    use core::alloc::{GlobalAlloc, Layout};
+    use core::cell::Cell;
+    use std::alloc::System;
+
+    use super::AllocStats;
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Counter {
+        stats: AllocStats,
+        /// Bytes allocated minus bytes freed since counting started
+        current: isize,
+    }
+
+    thread_local! {
+        // No lazy initialisation nor destructor, so that it can be used from
+        // within the allocator
+        static COUNTER: Cell<Option<Counter>> = const { Cell::new(None) };
+    }
+
+    pub(super) fn start() {
+        COUNTER.with(|counter| {
+            counter.set(Some(Counter {
+                stats: AllocStats::default(),
+                current: 0,
+            }))
+        });
+    }
+
+    pub(super) fn stop() -> AllocStats {
+        COUNTER.with(|counter| counter.take().map(|c| c.stats).unwrap_or_default())
+    }
+
+    /// Stop counting until [`resume`], returning the counter if measuring
+    #[cfg(any(feature = "trace", test))]
+    pub(super) fn pause() -> Option<Counter> {
+        COUNTER.with(|counter| counter.take())
+    }
+
+    #[cfg(any(feature = "trace", test))]
+    pub(super) fn resume(paused: Option<Counter>) {
+        COUNTER.with(|counter| counter.set(paused));
+    }
+
+    fn record(allocated: usize, freed: usize) {
+        let _ = COUNTER.try_with(|counter| {
+            if let Some(mut c) = counter.get() {
+                if allocated > 0 {
+                    c.stats.count += 1;
+                    c.stats.bytes += allocated;
+                }
+
+                c.current += allocated as isize - freed as isize;
+                c.stats.peak = c.stats.peak.max(c.current.max(0) as usize);
+                counter.set(Some(c));
+            }
+        });
+    }
+
+    /// [`System`] allocator counting the allocations made while measuring
+    pub struct CountingAlloc;
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            record(layout.size(), 0);
+            System.alloc(layout)
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            record(layout.size(), 0);
+            System.alloc_zeroed(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            record(0, layout.size());
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            record(new_size, layout.size());
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[test]
+    fn counts() {
+        let (_, stats) = super::measure(|| {
+            let mut v = Vec::<u8>::with_capacity(10);
+            v.extend_from_slice(&[0; 30]);
+            drop(v);
+            Box::new(0u32)
+        });
+
+        assert_eq!(
+            stats,
+            Some(AllocStats {
+                count: 3,
+                bytes: 44,
+                peak: 30,
+            })
+        );
+
+        assert_eq!(super::measure(|| 1 + 1).1, Some(AllocStats::default()));
+
+        let (_, stats) = super::measure(|| {
+            let uncounted = super::uncounted(|| Box::new(0u64));
+            (uncounted, Box::new(0u32))
+        });
+
+        assert_eq!(
+            stats,
+            Some(AllocStats {
+                count: 1,
+                bytes: 4,
+                peak: 4,
+            })
+        );
+    }
+}
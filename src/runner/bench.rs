@@ -0,0 +1,147 @@
+use core::hint::black_box;
+use std::time::{Duration, Instant};
+
+use aoc2022::parse::ParseError;
+use aoc2022::{Params, Parts, Solution};
+use bumpalo::Bump;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchOptions {
+    pub iterations: usize,
+    pub warmup: usize,
+    /// Time after which a day stops being measured, even if it hasn't run all
+    /// its iterations yet
+    pub budget: Duration,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        BenchOptions {
+            iterations: 100,
+            warmup: 3,
+            budget: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub runs: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub p99: Duration,
+}
+
+impl Stats {
+    /// Sorts the samples in place to compute the statistics
+    pub fn from_samples(samples: &mut [Duration]) -> Stats {
+        if samples.is_empty() {
+            return Stats::default();
+        }
+
+        samples.sort_unstable();
+
+        let runs = samples.len();
+        let percentile = |p: usize| samples[((runs * p + 99) / 100).max(1) - 1];
+
+        Stats {
+            runs,
+            min: samples[0],
+            median: samples[runs / 2],
+            mean: samples.iter().sum::<Duration>() / runs as u32,
+            p99: percentile(99),
+        }
+    }
+
+    fn add(self, other: Stats) -> Stats {
+        Stats {
+            runs: self.runs + other.runs,
+            min: self.min + other.min,
+            median: self.median + other.median,
+            mean: self.mean + other.mean,
+            p99: self.p99 + other.p99,
+        }
+    }
+}
+
+/// Time `options.iterations` runs of a day, resetting the arena between runs
+///
+/// `samples` must have a capacity of at least `options.iterations` so that
+/// this doesn't allocate. Stops at the first run failing to parse the input.
+pub fn bench(
+    solution: &dyn Solution,
+    bump: &mut Bump,
+    input: &str,
+    parts: Parts,
+    params: Params,
+    options: &BenchOptions,
+    samples: &mut Vec<Duration>,
+) -> Result<Stats, ParseError> {
+    samples.clear();
+
+    for _ in 0..options.warmup {
+        bump.reset();
+        black_box(solution.solve_with(bump, black_box(input), parts, params))?;
+    }
+
+    let bench_start = Instant::now();
+
+    while samples.len() < options.iterations && bench_start.elapsed() < options.budget {
+        bump.reset();
+        let start = Instant::now();
+        black_box(solution.solve_with(bump, black_box(input), parts, params))?;
+        samples.push(start.elapsed());
+    }
+
+    Ok(Stats::from_samples(samples))
+}
+
+pub fn print_table(stats: &[(u8, Stats)]) {
+    println!(
+        "{:>5} | {:>10} | {:>10} | {:>10} | {:>10} | {:>5}",
+        "Day", "min", "median", "mean", "p99", "runs"
+    );
+    println!("------|------------|------------|------------|------------|------");
+
+    let row = |name: &dyn core::fmt::Display, s: &Stats| {
+        println!(
+            "{:>5} | {:>10} | {:>10} | {:>10} | {:>10} | {:>5}",
+            name,
+            format!("{:.1?}", s.min),
+            format!("{:.1?}", s.median),
+            format!("{:.1?}", s.mean),
+            format!("{:.1?}", s.p99),
+            s.runs
+        );
+    };
+
+    for (day, s) in stats {
+        row(day, s);
+    }
+
+    let total = stats
+        .iter()
+        .fold(Stats::default(), |total, (_, s)| total.add(*s));
+
+    row(&"Total", &total);
+}
+
+#[test]
+fn stats() {
+    let ms = Duration::from_millis;
+
+    let mut samples = (1..=100).rev().map(ms).collect::<Vec<_>>();
+    let stats = Stats::from_samples(&mut samples);
+
+    assert_eq!(stats.runs, 100);
+    assert_eq!(stats.min, ms(1));
+    assert_eq!(stats.median, ms(51));
+    assert_eq!(stats.mean, Duration::from_micros(50500));
+    assert_eq!(stats.p99, ms(99));
+
+    let stats = Stats::from_samples(&mut [ms(3)]);
+    assert_eq!(stats.p99, ms(3));
+
+    assert_eq!(Stats::from_samples(&mut []), Stats::default());
+}
@@ -0,0 +1,105 @@
+use aoc2022::parse::ParseError;
+use aoc2022::{Params, Parts, Solution};
+use bumpalo::Bump;
+
+/// Smallest capacity an arena needs for `solution` to run without growing
+///
+/// Arenas get a new chunk from the global allocator rather than failing when
+/// they are full, so this binary searches for the smallest capacity which
+/// didn't need one. Freeing the last allocation rewinds an arena, which is why
+/// the bytes used once a day returned aren't enough to size it.
+pub fn measure(
+    solution: &dyn Solution,
+    input: &str,
+    parts: Parts,
+    params: Params,
+) -> Result<usize, ParseError> {
+    let fits = |capacity: usize| -> Result<bool, ParseError> {
+        let bump = Bump::with_capacity(capacity);
+        let allocated = bump.allocated_bytes();
+        solution.solve_with(&bump, input, parts, params)?;
+        Ok(bump.allocated_bytes() == allocated)
+    };
+
+    // Invariant: `low` doesn't fit, `high` fits
+    let mut low = 0;
+    let mut high = 1024;
+
+    while !fits(high)? {
+        low = high;
+        high *= 2;
+    }
+
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+
+        if fits(mid)? {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+
+    Ok(high)
+}
+
+pub fn print_table(capacities: &[(u8, usize)], current: usize) {
+    println!("Day | Min capacity");
+    println!("----|-------------");
+
+    for (day, capacity) in capacities {
+        println!("{day:>3} | {capacity:>12}");
+    }
+
+    let needed = capacities.iter().map(|&(_, c)| c).max().unwrap_or_default();
+
+    println!("\nMinimum ALLOCATOR_CAPACITY: {needed} bytes (currently {current} bytes)");
+}
+
+#[test]
+fn measure_capacity() {
+    struct Fill(usize);
+
+    impl Solution for Fill {
+        fn day(&self) -> u8 {
+            1
+        }
+
+        fn title(&self) -> &'static str {
+            "Fill"
+        }
+
+        fn needs_allocator(&self) -> bool {
+            true
+        }
+
+        fn solve_phases<'bump>(
+            &self,
+            bump: &'bump Bump,
+            _input: &str,
+            _parts: Parts,
+            _params: Params,
+            _phase: &mut dyn FnMut(aoc2022::Phase),
+        ) -> Result<aoc2022::Answers<'bump>, ParseError> {
+            // Freed straight away, so only visible as a high-water mark
+            let mut scratch = bumpalo::collections::Vec::<u8>::with_capacity_in(self.0, bump);
+            scratch.push(1);
+            drop(scratch);
+
+            Ok((aoc2022::Answer::None, aoc2022::Answer::None))
+        }
+    }
+
+    let small = measure(&Fill(100), "", Parts::BOTH, Params::NONE).unwrap();
+    let large = measure(&Fill(100_000), "", Parts::BOTH, Params::NONE).unwrap();
+
+    // Chunks are rounded up to whole pages
+    assert!(small < 1024);
+    assert!(large > 100_000 - 4096);
+    assert!(large <= 100_000);
+
+    let bump = Bump::with_capacity(large);
+    let allocated = bump.allocated_bytes();
+    Fill(100_000).solve(&bump, "").unwrap();
+    assert_eq!(bump.allocated_bytes(), allocated);
+}
@@ -0,0 +1,153 @@
+use std::path::{Path, PathBuf};
+
+use super::report::Report;
+
+/// Answers we expect for a day, as stored in a `.answers` file next to its input
+///
+/// The first line holds the answer to part 1 and the rest of the file the
+/// answer to part 2, so that multi-line answers (such as day 10's CRT) can be
+/// stored verbatim. A missing or empty part is left unchecked.
+#[derive(Debug, Default, PartialEq)]
+pub struct Expected<'a> {
+    pub part1: Option<&'a str>,
+    pub part2: Option<&'a str>,
+}
+
+impl<'a> Expected<'a> {
+    pub fn parse(content: &'a str) -> Expected<'a> {
+        let (part1, part2) = content.split_once('\n').unwrap_or((content, ""));
+        let non_empty = |s: &'a str| Some(s.trim()).filter(|s| !s.is_empty());
+
+        Expected {
+            part1: non_empty(part1),
+            part2: non_empty(part2),
+        }
+    }
+}
+
+pub fn answers_path(input_path: &Path) -> PathBuf {
+    input_path.with_extension("answers")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Pass,
+    Fail,
+    Unchecked,
+    Error,
+}
+
+impl Status {
+    /// Status of a part, a requested part with no answer failing when one is
+    /// expected
+    fn of(expected: Option<&str>, requested: bool, actual: Option<&str>) -> Status {
+        match (expected, actual) {
+            _ if !requested => Status::Unchecked,
+            (None, _) => Status::Unchecked,
+            (Some(expected), Some(actual)) if expected == actual.trim() => Status::Pass,
+            (Some(_), _) => Status::Fail,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Pass => "pass",
+            Status::Fail => "FAIL",
+            Status::Unchecked => "-",
+            Status::Error => "ERROR",
+        }
+    }
+}
+
+/// Print a pass/fail table of the report against the expected answers
+///
+/// Returns whether every checked answer matched, a day whose input couldn't be
+/// parsed counting as a mismatch
+pub fn check(report: &Report, input_path: impl Fn(u8) -> PathBuf) -> bool {
+    let mut mismatches = Vec::new();
+
+    println!("Day | Part 1 | Part 2");
+    println!("----|--------|-------");
+
+    for result in report.days() {
+        let path = answers_path(&input_path(result.day));
+        let content = std::fs::read_to_string(&path).unwrap_or_else(|err| {
+            eprintln!("Warn: {err} on path {}", path.display());
+            String::new()
+        });
+        let expected = Expected::parse(&content);
+
+        let actual1 = report.part1(result);
+        let actual2 = report.part2(result);
+        let (status1, status2) = match result.error {
+            Some(_) => (Status::Error, Status::Error),
+            None => (
+                Status::of(expected.part1, result.parts.part1, actual1),
+                Status::of(expected.part2, result.parts.part2, actual2),
+            ),
+        };
+
+        println!(
+            "{:>3} | {:<6} | {}",
+            result.day,
+            status1.as_str(),
+            status2.as_str()
+        );
+
+        if status1 == Status::Fail {
+            mismatches.push((result.day, 1, expected.part1.unwrap().to_owned(), actual1));
+        }
+
+        if status2 == Status::Fail {
+            mismatches.push((result.day, 2, expected.part2.unwrap().to_owned(), actual2));
+        }
+    }
+
+    for (day, part, expected, actual) in mismatches.iter() {
+        println!("\nDay {day} part {part}:");
+        println!("expected: {expected}");
+        println!("actual:   {}", actual.map_or("no answer", str::trim));
+    }
+
+    for err in report.errors() {
+        println!("\nError: {err}");
+    }
+
+    mismatches.is_empty() && report.errors().next().is_none()
+}
+
+#[test]
+fn parse_expected() {
+    assert_eq!(
+        Expected::parse("24000\n45000\n"),
+        Expected {
+            part1: Some("24000"),
+            part2: Some("45000"),
+        }
+    );
+    assert_eq!(
+        Expected::parse("13140\n##..##\n#..#..\n"),
+        Expected {
+            part1: Some("13140"),
+            part2: Some("##..##\n#..#.."),
+        }
+    );
+    assert_eq!(
+        Expected::parse("2=-1=0\n"),
+        Expected {
+            part1: Some("2=-1=0"),
+            part2: None,
+        }
+    );
+    assert_eq!(Expected::parse(""), Expected::default());
+}
+
+#[test]
+fn part_status() {
+    assert_eq!(Status::of(Some("42"), true, Some("42\n")), Status::Pass);
+    assert_eq!(Status::of(Some("42"), true, Some("24")), Status::Fail);
+    assert_eq!(Status::of(Some("42"), true, None), Status::Fail);
+    assert_eq!(Status::of(Some("42"), false, None), Status::Unchecked);
+    assert_eq!(Status::of(None, true, Some("42")), Status::Unchecked);
+    assert_eq!(Status::of(None, true, None), Status::Unchecked);
+}
@@ -0,0 +1,344 @@
+use core::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::bench::BenchOptions;
+use super::config::DEFAULT_CONFIG_PATH;
+use super::history::{CompareOptions, DEFAULT_HISTORY_PATH};
+use super::report::Format;
+use super::selection::Selection;
+use super::{default_input_path, DEFAULT_INPUT_DIR, STDIN_PATH};
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub days: Selection,
+    pub input_path: Option<PathBuf>,
+    pub input_dir: Option<PathBuf>,
+    /// Config file to read instead of the default one
+    pub config: Option<PathBuf>,
+    pub check: bool,
+    /// Run the examples from the puzzles instead of the inputs
+    pub example: bool,
+    /// Run the days again whenever their input or answers change
+    pub watch: bool,
+    /// Fail when a day makes global allocations, needs the `count-alloc` feature
+    pub strict_alloc: bool,
+    /// Find the arena capacity needed instead of reporting answers
+    pub measure_capacity: bool,
+    pub format: Format,
+    pub bench: Option<BenchOptions>,
+    /// Compare the latest benchmarks with earlier ones instead of running days
+    pub compare: Option<CompareOptions>,
+    /// File the benchmarks are appended to and compared from
+    pub history: Option<PathBuf>,
+    /// Number of threads running days in parallel
+    pub jobs: Option<NonZeroUsize>,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut bench = BenchOptions::default();
+        let mut bench_flags = false;
+        let mut compare = CompareOptions::default();
+        let mut compare_flags = false;
+        let mut positional = 0;
+        let mut args = args.peekable();
+
+        match args.peek().map(String::as_str) {
+            Some("bench") => options.bench = Some(bench),
+            Some("compare") => options.compare = Some(compare),
+            _ => {}
+        }
+
+        if options.bench.is_some() || options.compare.is_some() {
+            args.next();
+        }
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--check" => options.check = true,
+                "--example" => options.example = true,
+                "--watch" => options.watch = true,
+                "--measure-capacity" => options.measure_capacity = true,
+                "--strict-alloc" if cfg!(feature = "count-alloc") => options.strict_alloc = true,
+                "--strict-alloc" => {
+                    return Err("--strict-alloc needs the count-alloc feature to be enabled".into())
+                }
+                "--format" => options.format = flag_value(&arg, args.next())?,
+                "--input-dir" => options.input_dir = Some(flag_value(&arg, args.next())?),
+                "--config" => options.config = Some(flag_value(&arg, args.next())?),
+                "--jobs" | "-j" => options.jobs = Some(flag_value(&arg, args.next())?),
+                "--iterations" => {
+                    bench.iterations = flag_value(&arg, args.next())?;
+                    bench_flags = true;
+                }
+                "--warmup" => {
+                    bench.warmup = flag_value(&arg, args.next())?;
+                    bench_flags = true;
+                }
+                "--budget-ms" => {
+                    bench.budget = Duration::from_millis(flag_value(&arg, args.next())?);
+                    bench_flags = true;
+                }
+                "--threshold" => {
+                    compare.threshold = flag_value(&arg, args.next())?;
+                    compare_flags = true;
+                }
+                "--history" => options.history = Some(flag_value(&arg, args.next())?),
+                flag if flag.starts_with("--") => return Err(format!("Unknown flag {flag}")),
+                days if positional == 0 => {
+                    options.days = days.parse()?;
+                    positional += 1;
+                }
+                path if positional == 1 => {
+                    options.input_path = Some(path.into());
+                    positional += 1;
+                }
+                extra => return Err(format!("Unexpected argument {extra:?}")),
+            }
+        }
+
+        match options.bench.as_mut() {
+            Some(options) => *options = bench,
+            None if bench_flags => {
+                return Err("--iterations, --warmup and --budget-ms need bench mode".into())
+            }
+            None => {}
+        }
+
+        match options.compare.as_mut() {
+            Some(options) => *options = compare,
+            None if compare_flags => return Err("--threshold needs compare mode".into()),
+            None => {}
+        }
+
+        if options.history.is_some() && options.bench.is_none() && options.compare.is_none() {
+            return Err("--history needs bench or compare mode".into());
+        }
+
+        if options.compare.is_some()
+            && (options.check
+                || options.example
+                || options.watch
+                || options.measure_capacity
+                || options.jobs.is_some()
+                || options.input_dir.is_some()
+                || options.config.is_some()
+                || options.input_path.is_some())
+        {
+            return Err("compare only reads the history, it doesn't run any day".into());
+        }
+
+        if options.example && (options.bench.is_some() || options.check || options.measure_capacity)
+        {
+            return Err("--example can't be used with bench, --check or --measure-capacity".into());
+        }
+
+        if options.example && options.input_path.is_some() {
+            return Err("--example runs the built-in examples, not INPUT_PATH".into());
+        }
+
+        if options.watch && (options.bench.is_some() || options.example || options.measure_capacity)
+        {
+            return Err("--watch can't be used with bench, --example or --measure-capacity".into());
+        }
+
+        if options.watch && options.input_path.as_deref() == Some(Path::new(STDIN_PATH)) {
+            return Err("--watch needs input files, not the standard input".into());
+        }
+
+        if options.bench.is_some() && options.measure_capacity {
+            return Err("--measure-capacity can't be used in bench mode".into());
+        }
+
+        if options.bench.is_some() && options.jobs.is_some() {
+            return Err(
+                "--jobs isn't supported in bench mode, where days run one at a time".into(),
+            );
+        }
+
+        if options.input_path.is_some() && options.days.single_day().is_none() {
+            return Err("INPUT_PATH needs a single day to be selected".into());
+        }
+
+        Ok(options)
+    }
+
+    pub fn history_path(&self) -> &Path {
+        self.history
+            .as_deref()
+            .unwrap_or(Path::new(DEFAULT_HISTORY_PATH))
+    }
+
+    /// Where to read the input of `day` from
+    pub fn input_path(&self, day: u8) -> PathBuf {
+        match &self.input_path {
+            Some(path) if self.days.single_day() == Some(day) => path.clone(),
+            _ => {
+                let input_dir = self.input_dir.as_deref();
+                default_input_path(input_dir.unwrap_or(Path::new(DEFAULT_INPUT_DIR)), day)
+            }
+        }
+    }
+}
+
+fn flag_value<T>(flag: &str, value: Option<String>) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: core::fmt::Display,
+{
+    let value = value.ok_or_else(|| format!("Missing value for {flag}"))?;
+    value
+        .parse()
+        .map_err(|err| format!("Invalid value {value:?} for {flag}: {err}"))
+}
+
+pub fn usage(program_name: &str) -> String {
+    format!(
+        r#"
+Usage:
+    {program_name} [--check] [--format FORMAT] [--input-dir DIR] [--config PATH] [--jobs N] [--strict-alloc] [DAYS] [INPUT_PATH]
+    {program_name} --measure-capacity [--input-dir DIR] [--config PATH] [DAYS] [INPUT_PATH]
+    {program_name} --example [DAYS]
+    {program_name} --watch [--input-dir DIR] [--config PATH] [DAYS] [INPUT_PATH]
+    {program_name} bench [--iterations N] [--warmup N] [--budget-ms MS] [--history PATH] [--input-dir DIR] [--config PATH] [DAYS] [INPUT_PATH]
+    {program_name} compare [--threshold PERCENT] [--history PATH] [DAYS]
+
+Defaults to all the days when none specified, and to reading `DIR/dayNN.txt`
+when no INPUT_PATH is given. An INPUT_PATH of `-` reads the standard input.
+
+DAYS is a comma separated list of days (`5`) or ranges of days (`1-10`), each
+optionally restricted to one part (`15:2`). INPUT_PATH needs a single day.
+
+Options:
+    --check         Compare the answers against the `.answers` file next to each input
+    --example       Run the examples from the puzzles and compare with their answers
+    --watch         Run days again when their input or `.answers` file changes
+    --format FORMAT Report as text (default), json, csv or markdown
+    --input-dir DIR Directory holding the `dayNN.txt` inputs (default `inputs`)
+    --config PATH   Input directory, arena capacity and parameters of the days
+                    (default `{DEFAULT_CONFIG_PATH}`, when it exists)
+    -j, --jobs N    Run days on N threads, each with its own arena (default 1)
+    --strict-alloc  Fail if a day allocates outside of its arena (count-alloc feature)
+    --measure-capacity
+                    Find the smallest arena each day fits in, without reporting answers
+
+Bench options:
+    --iterations N  Number of timed runs per day (default 100)
+    --warmup N      Number of untimed runs per day before measuring (default 3)
+    --budget-ms MS  Stop measuring a day after this long (default 1000)
+    --history PATH  File the results are appended to (default `{DEFAULT_HISTORY_PATH}`)
+
+Compare options:
+    --threshold PERCENT
+                    Slowdown of a day's median above which it fails (default 10)
+    --history PATH  File the results are read from (default `{DEFAULT_HISTORY_PATH}`)
+
+compare checks the latest benchmark of each day against the latest one of the
+same input on another git revision, and fails if any day regressed.
+"#
+    )
+}
+
+#[test]
+fn parse_options() {
+    let parse = |args: &[&str]| Options::parse(args.iter().map(|s| s.to_string()));
+
+    assert_eq!(parse(&[]), Ok(Options::default()));
+    assert_eq!(
+        parse(&["--check", "10", "my_input.txt"]),
+        Ok(Options {
+            days: "10".parse().unwrap(),
+            input_path: Some("my_input.txt".into()),
+            check: true,
+            ..Options::default()
+        })
+    );
+    assert_eq!(
+        parse(&["bench", "--iterations", "10", "--budget-ms", "500", "3"]),
+        Ok(Options {
+            days: "3".parse().unwrap(),
+            bench: Some(BenchOptions {
+                iterations: 10,
+                budget: Duration::from_millis(500),
+                ..BenchOptions::default()
+            }),
+            ..Options::default()
+        })
+    );
+    assert_eq!(
+        parse(&["--format", "json"]),
+        Ok(Options {
+            format: Format::Json,
+            ..Options::default()
+        })
+    );
+    assert_eq!(
+        parse(&["--input-dir", "other", "7", "-"]),
+        Ok(Options {
+            days: "7".parse().unwrap(),
+            input_path: Some("-".into()),
+            input_dir: Some("other".into()),
+            ..Options::default()
+        })
+    );
+    assert!(parse(&["--format", "yaml"]).is_err());
+    assert!(parse(&["--input-dir"]).is_err());
+    assert_eq!(
+        parse(&["--config", "ci.toml"]).unwrap().config,
+        Some("ci.toml".into())
+    );
+    assert_eq!(parse(&["-j", "4"]).unwrap().jobs, NonZeroUsize::new(4));
+    assert!(parse(&["--jobs", "0"]).is_err());
+    assert!(parse(&["bench", "--jobs", "2"]).is_err());
+    assert!(parse(&["--measure-capacity"]).unwrap().measure_capacity);
+    assert!(parse(&["bench", "--measure-capacity"]).is_err());
+    assert!(parse(&["--example", "15:2"]).unwrap().example);
+    assert!(parse(&["--example", "--check"]).is_err());
+    assert!(parse(&["--example", "15", "my_input.txt"]).is_err());
+    assert!(parse(&["--watch", "7"]).unwrap().watch);
+    assert!(parse(&["--watch", "7", "-"]).is_err());
+    assert!(parse(&["--watch", "--example"]).is_err());
+    assert_eq!(
+        parse(&["--strict-alloc"]).is_ok(),
+        cfg!(feature = "count-alloc")
+    );
+    assert!(parse(&["ten"]).is_err());
+    assert!(parse(&["30"]).is_err());
+    assert!(parse(&["1-3", "my_input.txt"]).is_err());
+    assert!(parse(&["--unknown"]).is_err());
+    assert!(parse(&["--warmup", "1"]).is_err());
+    assert!(parse(&["bench", "--warmup"]).is_err());
+    assert_eq!(
+        parse(&["compare", "--threshold", "5.5", "--history", "h.tsv", "1-3"]),
+        Ok(Options {
+            days: "1-3".parse().unwrap(),
+            compare: Some(CompareOptions { threshold: 5.5 }),
+            history: Some("h.tsv".into()),
+            ..Options::default()
+        })
+    );
+    assert!(parse(&["--threshold", "5"]).is_err());
+    assert!(parse(&["--history", "h.tsv"]).is_err());
+    assert!(parse(&["compare", "--check"]).is_err());
+    assert!(parse(&["compare", "1", "my_input.txt"]).is_err());
+    assert!(parse(&["compare", "--config", "ci.toml"]).is_err());
+}
+
+#[test]
+fn input_paths() {
+    let parse = |args: &[&str]| Options::parse(args.iter().map(|s| s.to_string())).unwrap();
+
+    assert_eq!(parse(&[]).input_path(3), Path::new("inputs/day03.txt"));
+    assert_eq!(parse(&["3", "-"]).input_path(3), Path::new("-"));
+    assert_eq!(
+        parse(&["--input-dir", "theirs"]).input_path(12),
+        Path::new("theirs/day12.txt")
+    );
+    assert_eq!(
+        parse(&["--input-dir", "theirs", "3", "mine.txt"]).input_path(3),
+        Path::new("mine.txt")
+    );
+}
@@ -0,0 +1,305 @@
+use core::str::FromStr;
+use std::path::{Path, PathBuf};
+
+use aoc2022::Params;
+
+/// Read from the current directory when it exists and no other is given
+pub const DEFAULT_CONFIG_PATH: &str = "aoc.toml";
+
+/// Settings of the runner, each overriding a built-in default
+///
+/// The file is a small subset of TOML: `input_dir` and `arena_capacity` at the
+/// top, then a `[dayNN]` table with the integer parameters of each day:
+/// ```toml
+/// # Relative to the current directory
+/// input_dir = "other/inputs"
+/// arena_capacity = 524_288
+///
+/// [day15]
+/// row = 10
+/// bound = 20
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    pub input_dir: Option<PathBuf>,
+    pub arena_capacity: Option<usize>,
+    /// Parameters of each day, by day - 1
+    params: [Vec<(&'static str, i64)>; 25],
+}
+
+impl Config {
+    /// Read the config at `path`, or the default one if it exists
+    pub fn load(path: Option<&Path>) -> Result<Config, String> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => Path::new(DEFAULT_CONFIG_PATH),
+            None => return Ok(Config::default()),
+        };
+
+        std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|s| s.parse())
+            .map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    /// Parameters of `day`, those not set keeping the day's defaults
+    pub fn params(&self, day: u8) -> Params<'_> {
+        Params(&self.params[day as usize - 1])
+    }
+}
+
+impl FromStr for Config {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut config = Config::default();
+        // Day of the current table, none before the first one
+        let mut day = None;
+
+        for (n, line) in s.lines().enumerate() {
+            let error = |err: String| format!("line {}: {err}", n + 1);
+            let line = strip_comment(line).trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(table) = line.strip_prefix('[') {
+                let table = table
+                    .strip_suffix(']')
+                    .ok_or_else(|| error("expected ']'".into()))?
+                    .trim();
+
+                day = Some(parse_day(table).map_err(error)?);
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `key = value`".into()))?;
+            let key = key.trim();
+            let value = Value::parse(value.trim()).map_err(error)?;
+
+            match day {
+                None => config.set(key, value).map_err(error)?,
+                Some(day) => config.set_param(day, key, value).map_err(error)?,
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+impl Config {
+    fn set(&mut self, key: &str, value: Value) -> Result<(), String> {
+        match (key, value) {
+            ("input_dir", Value::Str(dir)) if self.input_dir.is_none() => {
+                self.input_dir = Some(dir.into())
+            }
+            ("arena_capacity", Value::Int(bytes)) if self.arena_capacity.is_none() => {
+                let bytes = usize::try_from(bytes)
+                    .map_err(|_| format!("invalid arena_capacity {bytes}"))?;
+                self.arena_capacity = Some(bytes);
+            }
+            ("input_dir", Value::Str(_)) | ("arena_capacity", Value::Int(_)) => {
+                return Err(format!("{key} is set twice"))
+            }
+            ("input_dir", _) => return Err("input_dir must be a string".into()),
+            ("arena_capacity", _) => return Err("arena_capacity must be an integer".into()),
+            _ => return Err(format!("unknown key {key:?}")),
+        }
+
+        Ok(())
+    }
+
+    fn set_param(&mut self, day: u8, key: &str, value: Value) -> Result<(), String> {
+        let params = aoc2022::solution(day).unwrap().params();
+
+        let Some(param) = params.iter().find(|param| param.name == key) else {
+            return Err(match params {
+                [] => format!("day {day} has no parameters"),
+                _ => format!(
+                    "day {day} has no parameter {key:?}, expected one of {}",
+                    params.iter().map(|param| param.name).collect::<Vec<_>>().join(", ")
+                ),
+            });
+        };
+
+        let Value::Int(value) = value else {
+            return Err(format!("{key} must be an integer"));
+        };
+
+        if !param.values.contains(&value) {
+            return Err(format!(
+                "{key} must be within {}..={} for day {day}, found {value}",
+                param.values.start(),
+                param.values.end()
+            ));
+        }
+
+        let params = &mut self.params[day as usize - 1];
+
+        if params.iter().any(|&(n, _)| n == param.name) {
+            return Err(format!("{key} is set twice for day {day}"));
+        }
+
+        params.push((param.name, value));
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Value {
+    Int(i64),
+    Str(String),
+}
+
+impl Value {
+    fn parse(s: &str) -> Result<Value, String> {
+        if let Some(s) = s.strip_prefix('\'') {
+            // Literal strings have no escapes
+            let s = s.strip_suffix('\'').ok_or("expected a closing '")?;
+            return Ok(Value::Str(s.to_owned()));
+        }
+
+        if let Some(s) = s.strip_prefix('"') {
+            let s = s.strip_suffix('"').ok_or("expected a closing \"")?;
+            let mut out = String::with_capacity(s.len());
+            let mut chars = s.chars();
+
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => match chars.next() {
+                        Some('\\') => out.push('\\'),
+                        Some('"') => out.push('"'),
+                        Some('n') => out.push('\n'),
+                        Some('t') => out.push('\t'),
+                        _ => return Err(format!("unsupported escape in {s:?}")),
+                    },
+                    '"' => return Err(format!("unescaped '\"' in {s:?}")),
+                    c => out.push(c),
+                }
+            }
+
+            return Ok(Value::Str(out));
+        }
+
+        // Underscores are allowed between digits, as in `10_000`
+        let digits = s.replace('_', "");
+
+        digits
+            .parse()
+            .map(Value::Int)
+            .map_err(|_| format!("expected an integer or a string, found {s:?}"))
+    }
+}
+
+/// The part of a line before a `#` which isn't in a string
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+
+    for (i, c) in line.char_indices() {
+        match (c, quote) {
+            ('#', None) => return &line[..i],
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            _ => {}
+        }
+    }
+
+    line
+}
+
+/// Day of a table named `dayNN`, which must have a solution
+fn parse_day(table: &str) -> Result<u8, String> {
+    let day = table
+        .strip_prefix("day")
+        .and_then(|day| day.parse::<u8>().ok())
+        .ok_or_else(|| format!("expected a table named dayNN, found {table:?}"))?;
+
+    match aoc2022::solution(day) {
+        Some(_) => Ok(day),
+        None => Err(format!("day {day} isn't solved yet")),
+    }
+}
+
+#[test]
+fn parse_config() {
+    let config: Config = r#"
+# Comments are ignored
+input_dir = "other # inputs" # even after a value
+arena_capacity = 524_288
+
+[day15]
+row = 10
+bound = 20
+
+[day7]
+disk_size = 1_000
+"#
+    .parse()
+    .unwrap();
+
+    assert_eq!(config.input_dir, Some("other # inputs".into()));
+    assert_eq!(config.arena_capacity, Some(524288));
+    assert_eq!(config.params(15), Params(&[("row", 10), ("bound", 20)]));
+    assert_eq!(config.params(7), Params(&[("disk_size", 1000)]));
+    assert_eq!(config.params(1), Params::NONE);
+
+    assert_eq!(
+        "input_dir = 'C:\\inputs'"
+            .parse::<Config>()
+            .unwrap()
+            .input_dir,
+        Some("C:\\inputs".into())
+    );
+    assert_eq!("".parse(), Ok(Config::default()));
+}
+
+#[test]
+fn config_errors() {
+    let error = |s: &str| s.parse::<Config>().unwrap_err();
+
+    assert_eq!(error("input = 1"), "line 1: unknown key \"input\"");
+    assert_eq!(error("input_dir = 1"), "line 1: input_dir must be a string");
+    assert_eq!(
+        error("arena_capacity = -1"),
+        "line 1: invalid arena_capacity -1"
+    );
+    assert_eq!(
+        error("\n[day15]\nrows = 1"),
+        "line 3: day 15 has no parameter \"rows\", expected one of row, bound"
+    );
+    assert_eq!(error("[day1]\nx = 1"), "line 2: day 1 has no parameters");
+    assert_eq!(
+        error("[day15]\nrow = '1'"),
+        "line 2: row must be an integer"
+    );
+    assert_eq!(
+        error("[day6]\npacket_marker = 0"),
+        "line 2: packet_marker must be within 1..=26 for day 6, found 0"
+    );
+    assert_eq!(
+        error("[day11]\npart1_rounds = -1"),
+        "line 2: part1_rounds must be within 0..=4294967295 for day 11, found -1"
+    );
+    assert_eq!(
+        error("[day7]\ndisk_size = 0"),
+        "line 2: disk_size must be within 1..=9223372036854775807 for day 7, found 0"
+    );
+    assert_eq!(
+        error("[day15]\nrow = 1\nrow = 2"),
+        "line 3: row is set twice for day 15"
+    );
+    assert_eq!(error("[day16]"), "line 1: day 16 isn't solved yet");
+    assert_eq!(
+        error("[fifteen]"),
+        "line 1: expected a table named dayNN, found \"fifteen\""
+    );
+    assert_eq!(error("row"), "line 1: expected `key = value`");
+    assert_eq!(
+        error("input_dir = \"inputs"),
+        "line 1: expected a closing \""
+    );
+}
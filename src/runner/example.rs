@@ -0,0 +1,83 @@
+use aoc2022::Answer;
+use bumpalo::Bump;
+
+use super::selection::Selection;
+
+/// Run the examples of the selected days, printing each answer next to the
+/// expected one
+///
+/// Returns whether all the answers matched
+pub fn run_examples(days: &Selection, bump: &mut Bump) -> bool {
+    let mut all_match = true;
+
+    for solution in aoc2022::SOLUTIONS {
+        let day = solution.day();
+        let parts = days.parts(day);
+
+        if parts.is_empty() {
+            continue;
+        }
+
+        for (n, example) in solution.examples().iter().enumerate() {
+            println!("Day {day} example {}:", n + 1);
+
+            bump.reset();
+
+            match solution.solve_with(bump, example.input, parts, example.params) {
+                Ok((part1, part2)) => {
+                    if parts.part1 {
+                        all_match &= print_answer(1, part1, example.part1);
+                    }
+
+                    if parts.part2 {
+                        all_match &= print_answer(2, part2, example.part2);
+                    }
+                }
+                Err(err) => {
+                    println!("  Error: {err}");
+                    all_match = false;
+                }
+            }
+        }
+    }
+
+    all_match
+}
+
+/// Returns whether the answer matched, or wasn't known
+fn print_answer(part: u8, answer: Answer, expected: Option<&str>) -> bool {
+    let answer = answer.to_string();
+    let answer = answer.trim();
+    let matches = expected.map_or(true, |expected| expected == answer);
+    let status = if matches { "" } else { " FAIL" };
+
+    match expected {
+        Some(expected) if answer.contains('\n') || expected.contains('\n') => {
+            println!("  part {part}:{status}\n{answer}\n  expected:\n{expected}")
+        }
+        Some(expected) => println!("  part {part}: {answer} (expected {expected}){status}"),
+        None => println!("  part {part}: {answer}"),
+    }
+
+    matches
+}
+
+#[test]
+fn examples_match() {
+    let mut bump = Bump::new();
+
+    assert!(run_examples(&Selection::default(), &mut bump));
+    assert!(aoc2022::SOLUTIONS
+        .iter()
+        .all(|solution| !solution.examples().is_empty()));
+}
+
+#[test]
+fn answers() {
+    assert!(print_answer(1, Answer::Int(1), Some("1")));
+    assert!(!print_answer(1, Answer::Int(2), Some("1")));
+    assert!(!print_answer(1, Answer::Str("2"), Some("1")));
+    assert!(print_answer(2, Answer::Art("\n#.\n.#\n"), Some("#.\n.#")));
+    assert!(!print_answer(2, Answer::Art("#.\n.#"), Some("#.\n##")));
+    assert!(print_answer(2, Answer::None, None));
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Layer collecting folded stacks, as read by flamegraph tools
+pub struct FoldedLayer {
+    stacks: Arc<Mutex<Stacks>>,
+}
+
+/// Writes the folded stacks to its file when dropped
+pub struct FoldedGuard {
+    stacks: Arc<Mutex<Stacks>>,
+    path: PathBuf,
+}
+
+/// Stacks of spans interned by their parent and name, so that only the first
+/// span of each stack allocates, and the frames of the open spans
+#[derive(Default)]
+struct Stacks {
+    stacks: Vec<Stack>,
+    ids: HashMap<(Option<usize>, &'static str), usize>,
+    /// Keyed by span id, kept outside of the span extensions which box each
+    /// value
+    frames: HashMap<u64, Frame>,
+}
+
+/// A stack of spans and its self time
+struct Stack {
+    parent: Option<usize>,
+    name: &'static str,
+    nanos: u64,
+}
+
+/// A span's place in the stack, and the timing of its current entry
+struct Frame {
+    /// Index of the stack of the span and its ancestors
+    stack: usize,
+    entered: Option<Instant>,
+    /// Time spent in child spans since entering
+    children: Duration,
+}
+
+/// Write the folded stacks to `path`, `trace-<timestamp>.folded` by default
+pub fn folded_layer(path: Option<PathBuf>) -> (FoldedLayer, FoldedGuard) {
+    let stacks = Arc::<Mutex<Stacks>>::default();
+    let path = path.unwrap_or_else(|| {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        format!("./trace-{}.folded", now.as_micros()).into()
+    });
+
+    (
+        FoldedLayer {
+            stacks: stacks.clone(),
+        },
+        FoldedGuard { stacks, path },
+    )
+}
+
+impl Stacks {
+    fn intern(&mut self, parent: Option<usize>, name: &'static str) -> usize {
+        let stacks = &mut self.stacks;
+
+        *self.ids.entry((parent, name)).or_insert_with(|| {
+            stacks.push(Stack {
+                parent,
+                name,
+                nanos: 0,
+            });
+            stacks.len() - 1
+        })
+    }
+
+    /// Names of the stack's spans, outermost first, `;` separated
+    fn folded(&self, stack: usize) -> String {
+        let Stack { parent, name, .. } = self.stacks[stack];
+
+        match parent {
+            Some(parent) => format!("{};{name}", self.folded(parent)),
+            None => name.to_owned(),
+        }
+    }
+}
+
+impl<S> Layer<S> for FoldedLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).unwrap();
+        let parent = span.parent().map(|parent| parent.id().into_u64());
+
+        let mut stacks = self.stacks.lock().unwrap();
+        let parent = parent
+            .and_then(|parent| stacks.frames.get(&parent))
+            .map(|frame| frame.stack);
+        let stack = stacks.intern(parent, span.name());
+
+        stacks.frames.insert(
+            id.into_u64(),
+            Frame {
+                stack,
+                entered: None,
+                children: Duration::ZERO,
+            },
+        );
+    }
+
+    fn on_enter(&self, id: &Id, _ctx: Context<'_, S>) {
+        if let Some(frame) = self.stacks.lock().unwrap().frames.get_mut(&id.into_u64()) {
+            frame.entered = Some(Instant::now());
+            frame.children = Duration::ZERO;
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        let parent = ctx
+            .span(id)
+            .and_then(|span| span.parent())
+            .map(|parent| parent.id().into_u64());
+
+        let mut stacks = self.stacks.lock().unwrap();
+        let Some(frame) = stacks.frames.get_mut(&id.into_u64()) else {
+            return;
+        };
+        let Some(entered) = frame.entered.take() else {
+            return;
+        };
+
+        let elapsed = entered.elapsed();
+        let self_time = elapsed.saturating_sub(frame.children);
+        let stack = frame.stack;
+
+        if let Some(frame) = parent.and_then(|parent| stacks.frames.get_mut(&parent)) {
+            frame.children += elapsed;
+        }
+
+        stacks.stacks[stack].nanos += self_time.as_nanos() as u64;
+    }
+
+    fn on_close(&self, id: Id, _ctx: Context<'_, S>) {
+        self.stacks.lock().unwrap().frames.remove(&id.into_u64());
+    }
+}
+
+impl FoldedGuard {
+    fn write(&self) -> io::Result<()> {
+        let stacks = self.stacks.lock().unwrap();
+
+        // Sorted so that the output of two runs can be diffed
+        let mut folded = (0..stacks.stacks.len())
+            .map(|stack| (stacks.folded(stack), stacks.stacks[stack].nanos))
+            .collect::<Vec<_>>();
+        folded.sort_unstable();
+
+        let mut out = BufWriter::new(File::create(&self.path)?);
+
+        for (stack, nanos) in folded {
+            writeln!(out, "{stack} {nanos}")?;
+        }
+
+        out.flush()
+    }
+}
+
+impl Drop for FoldedGuard {
+    fn drop(&mut self) {
+        if let Err(err) = self.write() {
+            eprintln!("Warn: {err} writing {}", self.path.display());
+        }
+    }
+}
+
+#[test]
+fn folded_stacks() {
+    use tracing_subscriber::prelude::*;
+
+    let path = std::env::temp_dir().join(format!("aoc2022-{}.folded", std::process::id()));
+    let (layer, guard) = folded_layer(Some(path.clone()));
+    let subscriber = tracing_subscriber::registry().with(layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        let outer = tracing::trace_span!("outer");
+        let _outer = outer.enter();
+
+        for _ in 0..2 {
+            tracing::trace_span!("inner").in_scope(|| {
+                std::thread::sleep(Duration::from_millis(1));
+            });
+        }
+    });
+
+    drop(guard);
+
+    let folded = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let lines = folded
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap())
+        .map(|(stack, nanos)| (stack, nanos.parse::<u64>().unwrap()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].0, "outer");
+    assert_eq!(lines[1].0, "outer;inner");
+    assert!(lines[1].1 >= 2_000_000);
+}
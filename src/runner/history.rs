@@ -0,0 +1,323 @@
+use core::fmt::{self, Display};
+use core::str::FromStr;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use aoc2022::Parts;
+
+use super::bench::Stats;
+use super::selection::Selection;
+
+pub const DEFAULT_HISTORY_PATH: &str = "bench_history.tsv";
+
+const HEADER: &str = "# rev\tinput\tday\tparts\truns\tmin_ns\tmedian_ns\tmean_ns\tp99_ns";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompareOptions {
+    /// Slowdown of the median, in percent, above which a day has regressed
+    pub threshold: f64,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        CompareOptions { threshold: 10.0 }
+    }
+}
+
+/// A day's benchmark, as one line of the history file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// Git revision the benchmark ran on, suffixed with `-dirty` when the
+    /// working tree had changes
+    pub rev: String,
+    pub input_hash: u64,
+    pub day: u8,
+    pub parts: Parts,
+    pub stats: Stats,
+}
+
+impl Record {
+    /// Benchmarks are only comparable for the same day, parts and input
+    fn same_key(&self, other: &Record) -> bool {
+        self.day == other.day && self.parts == other.parts && self.input_hash == other.input_hash
+    }
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts = match (self.parts.part1, self.parts.part2) {
+            (true, false) => "1",
+            (false, true) => "2",
+            _ => "12",
+        };
+        let s = &self.stats;
+
+        write!(
+            f,
+            "{}\t{:016x}\t{}\t{parts}\t{}\t{}\t{}\t{}\t{}",
+            self.rev,
+            self.input_hash,
+            self.day,
+            s.runs,
+            s.min.as_nanos(),
+            s.median.as_nanos(),
+            s.mean.as_nanos(),
+            s.p99.as_nanos()
+        )
+    }
+}
+
+impl FromStr for Record {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let fields = line.split('\t').collect::<Vec<_>>();
+
+        let &[rev, input_hash, day, parts, runs, min, median, mean, p99] = fields.as_slice() else {
+            return Err(format!("expected 9 fields, found {}", fields.len()));
+        };
+
+        let int = |field: &str| {
+            field
+                .parse::<u64>()
+                .map_err(|err| format!("invalid number {field:?}: {err}"))
+        };
+        let nanos = |field| int(field).map(Duration::from_nanos);
+
+        Ok(Record {
+            rev: rev.to_owned(),
+            input_hash: u64::from_str_radix(input_hash, 16)
+                .map_err(|err| format!("invalid input hash {input_hash:?}: {err}"))?,
+            day: day
+                .parse()
+                .map_err(|err| format!("invalid day {day:?}: {err}"))?,
+            parts: match parts {
+                "1" => Parts::PART1,
+                "2" => Parts::PART2,
+                "12" => Parts::BOTH,
+                _ => return Err(format!("invalid parts {parts:?}")),
+            },
+            stats: Stats {
+                runs: int(runs)? as usize,
+                min: nanos(min)?,
+                median: nanos(median)?,
+                mean: nanos(mean)?,
+                p99: nanos(p99)?,
+            },
+        })
+    }
+}
+
+/// Hash identifying an input, stable across runs and toolchains (FNV-1a)
+pub fn hash_input(input: &str) -> u64 {
+    input.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Revision of the working tree, or `unknown` outside of a git repository
+pub fn git_revision() -> String {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    };
+
+    match git(&["rev-parse", "--short=12", "HEAD"]) {
+        Some(rev) => match git(&["status", "--porcelain", "--untracked-files=no"]) {
+            Some(changes) if !changes.is_empty() => format!("{rev}-dirty"),
+            _ => rev,
+        },
+        None => "unknown".to_owned(),
+    }
+}
+
+pub fn append(path: &Path, records: &[Record]) -> io::Result<()> {
+    let is_new = !path.exists();
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    if is_new {
+        writeln!(file, "{HEADER}")?;
+    }
+
+    for record in records {
+        writeln!(file, "{record}")?;
+    }
+
+    Ok(())
+}
+
+/// Read all the records of a history file, oldest first
+pub fn load(path: &Path) -> io::Result<Vec<Record>> {
+    let mut records = Vec::new();
+
+    for (n, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let record = line.parse().map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {err}", n + 1))
+        })?;
+
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison<'a> {
+    pub base: &'a Record,
+    pub head: &'a Record,
+    /// Change of the median time, in percent
+    pub change: f64,
+}
+
+impl Comparison<'_> {
+    pub fn regressed(&self, options: &CompareOptions) -> bool {
+        self.change > options.threshold
+    }
+}
+
+/// Compare the latest benchmark of each selected day with the latest one of
+/// the same input from another revision
+///
+/// Days without such an earlier benchmark are left out.
+pub fn compare<'a>(records: &'a [Record], days: &Selection) -> Vec<Comparison<'a>> {
+    let mut comparisons = Vec::new();
+
+    for day in 1..=25 {
+        let parts = days.parts(day);
+
+        let Some(head_ix) = records
+            .iter()
+            .rposition(|r| r.day == day && r.parts == parts)
+        else {
+            continue;
+        };
+
+        let head = &records[head_ix];
+        let base = records[..head_ix]
+            .iter()
+            .rev()
+            .find(|r| r.same_key(head) && r.rev != head.rev);
+
+        if let Some(base) = base {
+            let change = head.stats.median.as_secs_f64() / base.stats.median.as_secs_f64() - 1.0;
+
+            comparisons.push(Comparison {
+                base,
+                head,
+                change: change * 100.0,
+            });
+        }
+    }
+
+    comparisons
+}
+
+/// Returns whether no day regressed
+pub fn print_comparisons(comparisons: &[Comparison], options: &CompareOptions) -> bool {
+    println!(
+        "{:>5} | {:>18} | {:>18} | {:>10} | {:>10} | {:>8}",
+        "Day", "base", "head", "median", "median", "change"
+    );
+    println!(
+        "------|--------------------|--------------------|------------|------------|---------"
+    );
+
+    let mut regressions = 0;
+
+    for c in comparisons {
+        let regressed = c.regressed(options);
+        regressions += regressed as usize;
+
+        println!(
+            "{:>5} | {:>18} | {:>18} | {:>10} | {:>10} | {:>+7.1}%{}",
+            c.head.day,
+            c.base.rev,
+            c.head.rev,
+            format!("{:.1?}", c.base.stats.median),
+            format!("{:.1?}", c.head.stats.median),
+            c.change,
+            if regressed { " REGRESSED" } else { "" }
+        );
+    }
+
+    if regressions > 0 {
+        println!(
+            "{regressions} day(s) slower by more than {}%",
+            options.threshold
+        );
+    }
+
+    regressions == 0
+}
+
+#[test]
+fn records() {
+    let record = Record {
+        rev: "0123456789ab-dirty".into(),
+        input_hash: hash_input("1\n2\n"),
+        day: 15,
+        parts: Parts::PART2,
+        stats: Stats {
+            runs: 100,
+            min: Duration::from_nanos(1),
+            median: Duration::from_nanos(2),
+            mean: Duration::from_nanos(3),
+            p99: Duration::from_nanos(4),
+        },
+    };
+
+    assert_eq!(hash_input(""), 0xcbf29ce484222325);
+    assert_eq!(record.to_string().parse(), Ok(record));
+    assert!("abc\t0\t1".parse::<Record>().is_err());
+    assert!("abc\tzz\t1\t12\t1\t1\t1\t1\t1".parse::<Record>().is_err());
+}
+
+#[test]
+fn comparisons() {
+    let record = |rev: &str, input_hash, day, median| Record {
+        rev: rev.into(),
+        input_hash,
+        day,
+        parts: Parts::BOTH,
+        stats: Stats {
+            median: Duration::from_micros(median),
+            ..Stats::default()
+        },
+    };
+
+    let records = [
+        record("a", 1, 1, 100),
+        record("a", 1, 2, 100),
+        record("b", 1, 1, 200),
+        record("b", 1, 1, 150),
+        // Another input can't be compared
+        record("b", 2, 2, 120),
+        record("c", 1, 2, 105),
+    ];
+
+    let comparisons = compare(&records, &Selection::default());
+    let options = CompareOptions::default();
+
+    assert_eq!(comparisons.len(), 2);
+    assert_eq!(comparisons[0].base, &records[0]);
+    assert_eq!(comparisons[0].head, &records[3]);
+    assert!(comparisons[0].regressed(&options));
+    assert_eq!(comparisons[1].base, &records[1]);
+    assert_eq!(comparisons[1].head, &records[5]);
+    assert!(!comparisons[1].regressed(&options));
+
+    assert!(compare(&records, &"2:1".parse().unwrap()).is_empty());
+}
@@ -0,0 +1,111 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use aoc2022::{Params, Parts, Phase, Solution};
+use assert_no_alloc::assert_no_alloc;
+use bumpalo::Bump;
+
+use super::alloc_count;
+use super::report::{Report, Times};
+use super::ArenaUsage;
+
+/// A day to run, with the parts selected, its input and its parameters
+pub type Job<'a> = (&'static dyn Solution, Parts, &'a str, Params<'a>);
+
+/// Run all the jobs, on `threads` threads when there are more than one
+///
+/// Each thread gets its own arena from `new_arena` and takes the next job
+/// until there are none left. The report ends up in day order.
+pub fn run(jobs: &[Job], threads: usize, new_arena: impl Fn() -> Bump + Sync, report: &mut Report) {
+    let next = AtomicUsize::new(0);
+    let report = Mutex::new(report);
+
+    if threads <= 1 {
+        worker(jobs, &next, &mut new_arena(), &report);
+    } else {
+        std::thread::scope(|scope| {
+            for _ in 0..threads.min(jobs.len()) {
+                scope.spawn(|| worker(jobs, &next, &mut new_arena(), &report));
+            }
+        });
+    }
+
+    report.into_inner().unwrap().sort_by_day();
+}
+
+/// Solve jobs until there are none left, checking that the days don't allocate
+/// but not the recording of their answers, which can grow the report
+fn worker(jobs: &[Job], next: &AtomicUsize, bump: &mut Bump, report: &Mutex<&mut Report>) {
+    while let Some(&(solution, parts, input, params)) =
+        jobs.get(next.fetch_add(1, Ordering::Relaxed))
+    {
+        bump.reset();
+
+        let (answers, allocs, time) = assert_no_alloc(|| {
+            let mut starts = [None; 3];
+            let start = Instant::now();
+            let (answers, allocs) = alloc_count::measure(|| {
+                solution.solve_phases(bump, input, parts, params, &mut |phase| {
+                    starts[phase as usize] = Some(Instant::now())
+                })
+            });
+
+            (answers, allocs, times(start, starts, Instant::now()))
+        });
+
+        report.lock().unwrap().record(
+            solution.day(),
+            parts,
+            answers,
+            time,
+            ArenaUsage::of(bump),
+            allocs,
+        );
+    }
+}
+
+/// Split the time from `start` to `end` between the phases, each of which
+/// lasts until the next one starts
+fn times(start: Instant, starts: [Option<Instant>; 3], end: Instant) -> Times {
+    let mut durations = [None; 3];
+    let mut until = end;
+
+    for phase in [Phase::Part2, Phase::Part1, Phase::Parse] {
+        if let Some(at) = starts[phase as usize] {
+            durations[phase as usize] = Some(until - at);
+            until = at;
+        }
+    }
+
+    let [parse, part1, part2] = durations;
+
+    Times {
+        total: end - start,
+        parse: parse.unwrap_or(Duration::ZERO),
+        part1,
+        part2,
+    }
+}
+
+#[test]
+fn phase_times() {
+    let start = Instant::now();
+    let at = |micros| start + Duration::from_micros(micros);
+
+    assert_eq!(
+        times(start, [Some(at(1)), None, Some(at(3))], at(10)),
+        Times {
+            total: Duration::from_micros(10),
+            parse: Duration::from_micros(2),
+            part1: None,
+            part2: Some(Duration::from_micros(7)),
+        }
+    );
+
+    // Failing to parse
+    assert_eq!(
+        times(start, [Some(at(0)), None, None], at(4)).parse,
+        Duration::from_micros(4)
+    );
+}
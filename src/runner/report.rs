@@ -0,0 +1,507 @@
+use core::fmt::{self, Display, Write};
+use core::ops::Range;
+use core::str::FromStr;
+use std::time::Duration;
+
+use aoc2022::parse::ParseError;
+use aoc2022::{Answer, Answers, Parts};
+
+use super::alloc_count::AllocStats;
+use super::ArenaUsage;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "markdown" | "md" => Ok(Format::Markdown),
+            _ => Err("expected one of text, json, csv or markdown".into()),
+        }
+    }
+}
+
+/// Time taken by a day, in total and by phase
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Times {
+    pub total: Duration,
+    pub parse: Duration,
+    /// Skipped parts have none
+    pub part1: Option<Duration>,
+    pub part2: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct DayResult {
+    pub day: u8,
+    pub time: Times,
+    /// Arena usage once the day returned, answers included
+    pub arena: ArenaUsage,
+    /// Only counted with the `count-alloc` feature
+    pub allocs: Option<AllocStats>,
+    pub error: Option<ParseError>,
+    /// Parts requested, whether or not they got an answer
+    pub parts: Parts,
+    /// Skipped parts, parts without an answer and days which failed have none
+    part1: Option<Range<usize>>,
+    part2: Option<Range<usize>>,
+}
+
+/// Answers of all the days run, formatted in a single buffer
+///
+/// The buffer is allocated upfront, recording answers only grows it when they
+/// don't fit
+pub struct Report {
+    answers: String,
+    days: Vec<DayResult>,
+}
+
+impl Report {
+    pub fn with_capacity(bytes: usize) -> Report {
+        Report {
+            answers: String::with_capacity(bytes),
+            days: Vec::with_capacity(25),
+        }
+    }
+
+    pub fn record(
+        &mut self,
+        day: u8,
+        parts: Parts,
+        answers: Result<Answers, ParseError>,
+        time: Times,
+        arena: ArenaUsage,
+        allocs: Option<AllocStats>,
+    ) {
+        let mut part1 = None;
+        let mut part2 = None;
+
+        if let Ok((answer1, answer2)) = answers {
+            if parts.part1 && !answer1.is_none() {
+                part1 = Some(self.write_answer(answer1));
+            }
+
+            if parts.part2 && !answer2.is_none() {
+                part2 = Some(self.write_answer(answer2));
+            }
+        }
+
+        self.days.push(DayResult {
+            day,
+            time,
+            arena,
+            allocs,
+            error: answers.err(),
+            parts,
+            part1,
+            part2,
+        });
+    }
+
+    fn write_answer(&mut self, answer: Answer) -> Range<usize> {
+        let start = self.answers.len();
+
+        // Start art on its own line in the text report
+        if let Answer::Art(_) = answer {
+            self.answers.push('\n');
+        }
+
+        write!(self.answers, "{answer}").unwrap();
+        start..self.answers.len()
+    }
+
+    pub fn days(&self) -> &[DayResult] {
+        &self.days
+    }
+
+    /// Days may be recorded in any order when run in parallel
+    pub fn sort_by_day(&mut self) {
+        self.days.sort_unstable_by_key(|result| result.day);
+    }
+
+    /// Most bytes any day left allocated in its arena
+    pub fn arena_retained(&self) -> usize {
+        self.days
+            .iter()
+            .map(|result| result.arena.retained)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Sum of the time taken by each day
+    pub fn total_time(&self) -> Duration {
+        self.days.iter().map(|result| result.time.total).sum()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ParseError> {
+        self.days.iter().filter_map(|result| result.error.as_ref())
+    }
+
+    pub fn part1(&self, result: &DayResult) -> Option<&str> {
+        result.part1.clone().map(|range| &self.answers[range])
+    }
+
+    pub fn part2(&self, result: &DayResult) -> Option<&str> {
+        result.part2.clone().map(|range| &self.answers[range])
+    }
+
+    pub fn formatted(&self, format: Format) -> Formatted<'_> {
+        Formatted {
+            report: self,
+            format,
+        }
+    }
+}
+
+pub struct Formatted<'a> {
+    report: &'a Report,
+    format: Format,
+}
+
+impl Display for Formatted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let report = self.report;
+
+        match self.format {
+            Format::Text => {
+                for result in report.days().iter().filter(|r| r.error.is_none()) {
+                    writeln!(
+                        f,
+                        "{}: {} {}",
+                        result.day,
+                        report.part1(result).unwrap_or("-"),
+                        report.part2(result).unwrap_or("-")
+                    )?;
+                }
+            }
+            Format::Json => {
+                writeln!(f, "[")?;
+
+                for (i, result) in report.days().iter().enumerate() {
+                    let separator = if i + 1 < report.days().len() { "," } else { "" };
+
+                    write!(f, r#"  {{"day": {}, "#, result.day)?;
+
+                    match &result.error {
+                        None => write!(
+                            f,
+                            r#""part1": {}, "part2": {}, "error": null, "#,
+                            Json(report.part1(result).map(str::trim)),
+                            Json(report.part2(result).map(str::trim)),
+                        )?,
+                        Some(err) => write!(
+                            f,
+                            r#""part1": null, "part2": null, "error": {}, "#,
+                            Json(Some(&err.to_string())),
+                        )?,
+                    }
+
+                    writeln!(
+                        f,
+                        r#""time_ns": {}, "parse_ns": {}, "part1_ns": {}, "part2_ns": {}, "bump_retained": {}, "bump_allocated": {}, "global_allocs": {}, "global_bytes": {}, "global_peak": {}}}{separator}"#,
+                        result.time.total.as_nanos(),
+                        result.time.parse.as_nanos(),
+                        Nanos(result.time.part1, "null"),
+                        Nanos(result.time.part2, "null"),
+                        result.arena.retained,
+                        result.arena.allocated,
+                        Count(result.allocs.map(|a| a.count), "null"),
+                        Count(result.allocs.map(|a| a.bytes), "null"),
+                        Count(result.allocs.map(|a| a.peak), "null"),
+                    )?;
+                }
+
+                writeln!(f, "]")?;
+            }
+            Format::Csv => {
+                writeln!(
+                    f,
+                    "day,part1,part2,error,time_ns,parse_ns,part1_ns,part2_ns,bump_retained,bump_allocated,global_allocs,global_bytes,global_peak"
+                )?;
+
+                for result in report.days() {
+                    let error = result.error.map(|err| err.to_string()).unwrap_or_default();
+
+                    writeln!(
+                        f,
+                        "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                        result.day,
+                        Csv(report.part1(result).unwrap_or_default().trim()),
+                        Csv(report.part2(result).unwrap_or_default().trim()),
+                        Csv(&error),
+                        result.time.total.as_nanos(),
+                        result.time.parse.as_nanos(),
+                        Nanos(result.time.part1, ""),
+                        Nanos(result.time.part2, ""),
+                        result.arena.retained,
+                        result.arena.allocated,
+                        Count(result.allocs.map(|a| a.count), ""),
+                        Count(result.allocs.map(|a| a.bytes), ""),
+                        Count(result.allocs.map(|a| a.peak), ""),
+                    )?;
+                }
+            }
+            Format::Markdown => {
+                writeln!(
+                    f,
+                    "| Day | Part 1 | Part 2 | Time | Parse | Part 1 time | Part 2 time | Bump retained | Bump allocated | Global allocs |"
+                )?;
+                writeln!(
+                    f,
+                    "|----:|--------|--------|-----:|------:|------------:|------------:|--------------:|---------------:|--------------:|"
+                )?;
+
+                for result in report.days() {
+                    let error = result.error.map(|err| format!("error: {err}"));
+
+                    writeln!(
+                        f,
+                        "| {} | {} | {} | {:.1?} | {:.1?} | {} | {} | {} | {} | {} |",
+                        result.day,
+                        Markdown(
+                            error
+                                .as_deref()
+                                .or(report.part1(result))
+                                .unwrap_or_default()
+                                .trim()
+                        ),
+                        Markdown(report.part2(result).unwrap_or_default().trim()),
+                        result.time.total,
+                        result.time.parse,
+                        Elapsed(result.time.part1),
+                        Elapsed(result.time.part2),
+                        result.arena.retained,
+                        result.arena.allocated,
+                        Count(result.allocs.map(|a| a.count), "-"),
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A number which might not have been measured
+struct Count(Option<usize>, &'static str);
+
+impl Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(n) => write!(f, "{n}"),
+            None => f.write_str(self.1),
+        }
+    }
+}
+
+/// Time of a phase in nanoseconds, when it ran
+struct Nanos(Option<Duration>, &'static str);
+
+impl Display for Nanos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(time) => write!(f, "{}", time.as_nanos()),
+            None => f.write_str(self.1),
+        }
+    }
+}
+
+/// Time of a phase for humans, `-` when it didn't run
+struct Elapsed(Option<Duration>);
+
+impl Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(time) => write!(f, "{time:.1?}"),
+            None => f.write_str("-"),
+        }
+    }
+}
+
+/// A string escaped as a JSON string literal, or `null`
+struct Json<'a>(Option<&'a str>);
+
+impl Display for Json<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(s) = self.0 else {
+            return f.write_str("null");
+        };
+
+        f.write_char('"')?;
+
+        for c in s.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+
+        f.write_char('"')
+    }
+}
+
+/// A CSV field, quoted only when needed
+struct Csv<'a>(&'a str);
+
+impl Display for Csv<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.contains([',', '"', '\n', '\r']) {
+            f.write_char('"')?;
+            for c in self.0.chars() {
+                if c == '"' {
+                    f.write_char('"')?;
+                }
+                f.write_char(c)?;
+            }
+            f.write_char('"')
+        } else {
+            f.write_str(self.0)
+        }
+    }
+}
+
+/// A markdown table cell, keeping multi-line answers in a single row
+struct Markdown<'a>(&'a str);
+
+impl Display for Markdown<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.contains('\n') {
+            f.write_str("<pre>")?;
+        }
+
+        for c in self.0.chars() {
+            match c {
+                '\n' => f.write_str("<br>")?,
+                '|' => f.write_str("\\|")?,
+                c => f.write_char(c)?,
+            }
+        }
+
+        if self.0.contains('\n') {
+            f.write_str("</pre>")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn formats() {
+    let mut report = Report::with_capacity(64);
+    report.record(
+        5,
+        Parts::BOTH,
+        Ok((Answer::Str("CMZ"), Answer::Str("MCD"))),
+        Times {
+            total: Duration::from_micros(12),
+            parse: Duration::from_micros(2),
+            part1: Some(Duration::from_micros(4)),
+            part2: Some(Duration::from_micros(6)),
+        },
+        ArenaUsage {
+            retained: 256,
+            allocated: 1024,
+        },
+        Some(AllocStats {
+            count: 2,
+            bytes: 64,
+            peak: 48,
+        }),
+    );
+    report.record(
+        10,
+        Parts::BOTH,
+        Ok((Answer::Int(13140), Answer::Art("#.\n.#"))),
+        Times {
+            total: Duration::from_millis(1),
+            ..Times::default()
+        },
+        ArenaUsage::default(),
+        None,
+    );
+
+    assert_eq!(
+        report.formatted(Format::Text).to_string(),
+        "5: CMZ MCD\n10: 13140 \n#.\n.#\n"
+    );
+    assert_eq!(
+        report.formatted(Format::Json).to_string(),
+        r##"[
+  {"day": 5, "part1": "CMZ", "part2": "MCD", "error": null, "time_ns": 12000, "parse_ns": 2000, "part1_ns": 4000, "part2_ns": 6000, "bump_retained": 256, "bump_allocated": 1024, "global_allocs": 2, "global_bytes": 64, "global_peak": 48},
+  {"day": 10, "part1": "13140", "part2": "#.\n.#", "error": null, "time_ns": 1000000, "parse_ns": 0, "part1_ns": null, "part2_ns": null, "bump_retained": 0, "bump_allocated": 0, "global_allocs": null, "global_bytes": null, "global_peak": null}
+]
+"##
+    );
+    assert_eq!(
+        report.formatted(Format::Csv).to_string(),
+        "day,part1,part2,error,time_ns,parse_ns,part1_ns,part2_ns,bump_retained,bump_allocated,global_allocs,global_bytes,global_peak\n5,CMZ,MCD,,12000,2000,4000,6000,256,1024,2,64,48\n10,13140,\"#.\n.#\",,1000000,0,,,0,0,,,\n"
+    );
+    assert_eq!(
+        report.formatted(Format::Markdown).to_string(),
+        "| Day | Part 1 | Part 2 | Time | Parse | Part 1 time | Part 2 time | Bump retained | Bump allocated | Global allocs |
+|----:|--------|--------|-----:|------:|------------:|------------:|--------------:|---------------:|--------------:|
+| 5 | CMZ | MCD | 12.0µs | 2.0µs | 4.0µs | 6.0µs | 256 | 1024 | 2 |
+| 10 | 13140 | <pre>#.<br>.#</pre> | 1.0ms | 0.0ns | - | - | 0 | 0 | - |
+"
+    );
+}
+
+#[test]
+fn skipped_parts() {
+    let mut report = Report::with_capacity(64);
+    report.record(
+        15,
+        Parts::PART2,
+        Ok((Answer::None, Answer::Int(42))),
+        Times {
+            part2: Some(Duration::ZERO),
+            ..Times::default()
+        },
+        ArenaUsage::default(),
+        None,
+    );
+
+    assert_eq!(report.formatted(Format::Text).to_string(), "15: - 42\n");
+    assert_eq!(
+        report.formatted(Format::Json).to_string(),
+        r#"[
+  {"day": 15, "part1": null, "part2": "42", "error": null, "time_ns": 0, "parse_ns": 0, "part1_ns": null, "part2_ns": 0, "bump_retained": 0, "bump_allocated": 0, "global_allocs": null, "global_bytes": null, "global_peak": null}
+]
+"#
+    );
+    assert_eq!(
+        report.formatted(Format::Csv).to_string(),
+        "day,part1,part2,error,time_ns,parse_ns,part1_ns,part2_ns,bump_retained,bump_allocated,global_allocs,global_bytes,global_peak\n15,,42,,0,0,,0,0,0,,,\n"
+    );
+
+    // A part without an answer is reported like a skipped one
+    report.record(
+        25,
+        Parts::BOTH,
+        Ok((Answer::Str("2=-1=0"), Answer::None)),
+        Times::default(),
+        ArenaUsage::default(),
+        None,
+    );
+
+    assert_eq!(
+        report.formatted(Format::Text).to_string(),
+        "15: - 42\n25: 2=-1=0 -\n"
+    );
+}
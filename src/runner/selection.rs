@@ -0,0 +1,126 @@
+use core::str::FromStr;
+
+use aoc2022::Parts;
+
+/// Days and parts to run, written like `1-10`, `5,7,13` or `15:2`
+///
+/// Defaults to both parts of every day
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    parts: [Parts; 25],
+}
+
+impl Default for Selection {
+    fn default() -> Self {
+        Selection {
+            parts: [Parts::BOTH; 25],
+        }
+    }
+}
+
+impl Selection {
+    /// Parts of `day` to run, none if it isn't selected
+    pub fn parts(&self, day: u8) -> Parts {
+        match day {
+            1..=25 => self.parts[day as usize - 1],
+            _ => Parts::NONE,
+        }
+    }
+
+    pub fn selects(&self, day: u8) -> bool {
+        !self.parts(day).is_empty()
+    }
+
+    /// The only day selected, if there is a single one
+    pub fn single_day(&self) -> Option<u8> {
+        let mut days = (1..=25).filter(|&day| self.selects(day));
+
+        match (days.next(), days.next()) {
+            (Some(day), None) => Some(day),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Selection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut selection = Selection {
+            parts: [Parts::NONE; 25],
+        };
+
+        for item in s.split(',') {
+            let (days, parts) = match item.split_once(':') {
+                None => (item, Parts::BOTH),
+                Some((days, "1")) => (days, Parts::PART1),
+                Some((days, "2")) => (days, Parts::PART2),
+                Some((_, part)) => return Err(format!("Invalid part {part:?}, expected 1 or 2")),
+            };
+
+            let (first, last) = match days.split_once('-') {
+                None => {
+                    let day = parse_day(days)?;
+
+                    if aoc2022::solution(day).is_none() {
+                        return Err(format!("Day {day} isn't solved yet"));
+                    }
+
+                    (day, day)
+                }
+                Some((first, last)) => (parse_day(first)?, parse_day(last)?),
+            };
+
+            if first > last {
+                return Err(format!("Empty range of days {days:?}"));
+            }
+
+            for day in first..=last {
+                let selected = &mut selection.parts[day as usize - 1];
+                *selected = selected.union(parts);
+            }
+        }
+
+        Ok(selection)
+    }
+}
+
+fn parse_day(day: &str) -> Result<u8, String> {
+    match day.parse::<u8>() {
+        Ok(n @ 1..=25) => Ok(n),
+        Ok(n) => Err(format!("Day {n} isn't between 1 and 25")),
+        Err(err) => Err(format!("Invalid day {day:?}: {err}")),
+    }
+}
+
+#[test]
+fn parse_selection() {
+    let days = |s: &str| {
+        let selection = s.parse::<Selection>().unwrap();
+        (1..=25)
+            .filter(|&day| selection.selects(day))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(days("7"), [7]);
+    assert_eq!(days("1-4"), [1, 2, 3, 4]);
+    assert_eq!(days("5,7,13"), [5, 7, 13]);
+    assert_eq!(days("24-25,1"), [1, 24, 25]);
+
+    let selection = "15:2,3,4:1,4:2,11-12:1".parse::<Selection>().unwrap();
+    assert_eq!(selection.parts(15), Parts::PART2);
+    assert_eq!(selection.parts(3), Parts::BOTH);
+    assert_eq!(selection.parts(4), Parts::BOTH);
+    assert_eq!(selection.parts(12), Parts::PART1);
+    assert_eq!(selection.parts(1), Parts::NONE);
+    assert_eq!(selection.parts(26), Parts::NONE);
+    assert_eq!(selection.single_day(), None);
+    assert_eq!("15:2".parse::<Selection>().unwrap().single_day(), Some(15));
+    assert_eq!(Selection::default().parts(25), Parts::BOTH);
+
+    for invalid in [
+        "0", "26", "300", "ten", "", "1-", "10-5", "3:3", "3:", "1,,2", "16",
+    ] {
+        assert!(invalid.parse::<Selection>().is_err(), "{invalid:?}");
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Time spent in all the spans sharing a name
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SpanStats {
+    pub calls: usize,
+    pub total: Duration,
+    /// Total time minus the time spent in child spans
+    pub self_time: Duration,
+}
+
+type Spans = Arc<Mutex<HashMap<&'static str, SpanStats>>>;
+
+/// Layer adding up the time spent in each span, by name
+pub struct SummaryLayer {
+    spans: Spans,
+}
+
+/// Prints the summary of the spans to stderr when dropped
+pub struct SummaryGuard {
+    spans: Spans,
+}
+
+/// Timing of the current entry into a span
+struct Entered {
+    at: Instant,
+    /// Time spent in child spans since entering
+    children: Duration,
+}
+
+pub fn summary_layer() -> (SummaryLayer, SummaryGuard) {
+    let spans = Spans::default();
+
+    (
+        SummaryLayer {
+            spans: spans.clone(),
+        },
+        SummaryGuard { spans },
+    )
+}
+
+impl<S> Layer<S> for SummaryLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).unwrap();
+        let mut spans = self.spans.lock().unwrap();
+
+        spans.entry(span.name()).or_default().calls += 1;
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        ctx.span(id).unwrap().extensions_mut().replace(Entered {
+            at: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).unwrap();
+
+        let Some(entered) = span.extensions_mut().remove::<Entered>() else {
+            return;
+        };
+
+        let elapsed = entered.at.elapsed();
+
+        if let Some(parent) = span.parent() {
+            if let Some(parent) = parent.extensions_mut().get_mut::<Entered>() {
+                parent.children += elapsed;
+            }
+        }
+
+        let mut spans = self.spans.lock().unwrap();
+        let stats = spans.entry(span.name()).or_default();
+
+        stats.total += elapsed;
+        stats.self_time += elapsed.saturating_sub(entered.children);
+    }
+}
+
+impl SummaryGuard {
+    /// Spans by name, the ones taking the most time by themselves first
+    pub fn summary(&self) -> Vec<(&'static str, SpanStats)> {
+        let mut summary = self
+            .spans
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(&name, &stats)| (name, stats))
+            .collect::<Vec<_>>();
+
+        summary.sort_unstable_by(|(a_name, a), (b_name, b)| {
+            b.self_time.cmp(&a.self_time).then(a_name.cmp(b_name))
+        });
+
+        summary
+    }
+}
+
+impl Drop for SummaryGuard {
+    fn drop(&mut self) {
+        let summary = self.summary();
+        let width = summary
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or_default()
+            .max("Span".len());
+
+        eprintln!(
+            "{:<width$} | {:>6} | {:>10} | {:>10}",
+            "Span", "calls", "total", "self"
+        );
+        eprintln!("{:-<width$}-|--------|------------|-----------", "");
+
+        for (name, stats) in summary {
+            eprintln!(
+                "{name:<width$} | {:>6} | {:>10} | {:>10}",
+                stats.calls,
+                format!("{:.1?}", stats.total),
+                format!("{:.1?}", stats.self_time),
+            );
+        }
+    }
+}
+
+#[test]
+fn summary() {
+    use tracing_subscriber::prelude::*;
+
+    let (layer, guard) = summary_layer();
+    let subscriber = tracing_subscriber::registry().with(layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        let outer = tracing::trace_span!("outer");
+        let _outer = outer.enter();
+
+        for _ in 0..2 {
+            tracing::trace_span!("inner").in_scope(|| {
+                std::thread::sleep(Duration::from_millis(5));
+            });
+        }
+    });
+
+    let summary = guard.summary();
+    let (names, stats): (Vec<_>, Vec<_>) = summary.iter().copied().unzip();
+
+    assert_eq!(names, ["inner", "outer"]);
+    assert_eq!(stats[0].calls, 2);
+    assert_eq!(stats[1].calls, 1);
+    assert!(stats[0].total >= Duration::from_millis(10));
+    assert!(stats[1].total >= stats[0].total);
+    assert!(stats[1].self_time < stats[0].self_time);
+}
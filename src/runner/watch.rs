@@ -0,0 +1,246 @@
+use core::fmt::{self, Display, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use aoc2022::parse::ParseError;
+use bumpalo::Bump;
+
+use super::check::{answers_path, Expected};
+use super::cli::Options;
+use super::config::Config;
+use super::jobs;
+use super::report::Report;
+use super::Input;
+
+/// How often the files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Outcome of running a day, kept to compare with the next run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub part1: Option<String>,
+    pub part2: Option<String>,
+    pub error: Option<ParseError>,
+    pub time: Duration,
+}
+
+struct Watched {
+    day: u8,
+    input: PathBuf,
+    answers: PathBuf,
+    mtimes: (Option<SystemTime>, Option<SystemTime>),
+    last: Option<Run>,
+}
+
+/// Run the selected days whenever their input or answers file changes, forever
+pub fn watch(options: &Options, config: &Config, new_arena: impl Fn() -> Bump + Sync) -> ! {
+    let mut watched = aoc2022::SOLUTIONS
+        .iter()
+        .map(|solution| solution.day())
+        .filter(|&day| options.days.selects(day))
+        .map(|day| {
+            let input = options.input_path(day);
+            let answers = answers_path(&input);
+
+            Watched {
+                day,
+                input,
+                answers,
+                mtimes: (None, None),
+                last: None,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    println!("Watching {} day(s), press Ctrl-C to stop", watched.len());
+
+    loop {
+        for w in watched.iter_mut() {
+            let mtimes = (modified(&w.input), modified(&w.answers));
+
+            if w.last.is_some() && mtimes == w.mtimes {
+                continue;
+            }
+
+            w.mtimes = mtimes;
+
+            let run = run_day(w.day, &w.input, options, config, &new_arena);
+            let answers = std::fs::read_to_string(&w.answers).unwrap_or_default();
+
+            print!(
+                "{}",
+                Diff {
+                    day: w.day,
+                    previous: w.last.as_ref(),
+                    current: &run,
+                    expected: Expected::parse(&answers),
+                }
+            );
+
+            w.last = Some(run);
+        }
+
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn run_day(
+    day: u8,
+    path: &Path,
+    options: &Options,
+    config: &Config,
+    new_arena: impl Fn() -> Bump + Sync,
+) -> Run {
+    let solution = aoc2022::solution(day).unwrap();
+    // Read rather than mapped, as the file is expected to change
+    let input = Input::read(path);
+    let mut report = Report::with_capacity(1024);
+
+    jobs::run(
+        &[(
+            solution,
+            options.days.parts(day),
+            input.as_str(),
+            config.params(day),
+        )],
+        1,
+        new_arena,
+        &mut report,
+    );
+
+    let result = &report.days()[0];
+
+    Run {
+        part1: report.part1(result).map(|s| s.trim().to_owned()),
+        part2: report.part2(result).map(|s| s.trim().to_owned()),
+        error: result.error,
+        time: result.time.total,
+    }
+}
+
+/// Changes between two runs of a day
+struct Diff<'a> {
+    day: u8,
+    previous: Option<&'a Run>,
+    current: &'a Run,
+    expected: Expected<'a>,
+}
+
+impl Display for Diff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let current = self.current;
+        let previous = self.previous;
+
+        writeln!(f, "Day {}:", self.day)?;
+
+        if let Some(err) = current.error {
+            return writeln!(f, "  Error: {err}");
+        }
+
+        let parts = [
+            (
+                1,
+                &current.part1,
+                previous.map(|p| &p.part1),
+                self.expected.part1,
+            ),
+            (
+                2,
+                &current.part2,
+                previous.map(|p| &p.part2),
+                self.expected.part2,
+            ),
+        ];
+
+        for (part, answer, previous, expected) in parts {
+            let Some(answer) = answer else { continue };
+
+            let mut line = String::new();
+
+            match previous {
+                Some(Some(previous)) if previous != answer => {
+                    write!(line, "{} -> {}", Multiline(previous), Multiline(answer))?
+                }
+                Some(Some(_)) => write!(line, "{} (unchanged)", Multiline(answer))?,
+                _ => write!(line, "{}", Multiline(answer))?,
+            }
+
+            match expected {
+                Some(expected) if expected == answer => line.push_str(" pass"),
+                Some(expected) => write!(line, " FAIL, expected {}", Multiline(expected))?,
+                None => {}
+            }
+
+            writeln!(f, "  part {part}: {line}")?;
+        }
+
+        match previous.map(|p| p.time) {
+            Some(before) if !before.is_zero() => {
+                let change = current.time.as_secs_f64() / before.as_secs_f64() - 1.0;
+                writeln!(
+                    f,
+                    "  time: {before:.1?} -> {:.1?} ({:+.1}%)",
+                    current.time,
+                    change * 100.0
+                )
+            }
+            _ => writeln!(f, "  time: {:.1?}", current.time),
+        }
+    }
+}
+
+/// Puts answers spanning multiple lines, like day 10's, on their own lines
+struct Multiline<'a>(&'a str);
+
+impl Display for Multiline<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.contains('\n') {
+            write!(f, "\n{}\n", self.0)
+        } else {
+            f.write_str(self.0)
+        }
+    }
+}
+
+#[test]
+fn diff() {
+    let ms = Duration::from_millis;
+    let run = |part1: &str, part2: &str, time| Run {
+        part1: Some(part1.into()),
+        part2: Some(part2.into()),
+        error: None,
+        time,
+    };
+
+    let first = run("95437", "24933642", ms(2));
+    let second = run("95438", "24933642", ms(1));
+
+    assert_eq!(
+        Diff {
+            day: 7,
+            previous: None,
+            current: &first,
+            expected: Expected::default(),
+        }
+        .to_string(),
+        "Day 7:\n  part 1: 95437\n  part 2: 24933642\n  time: 2.0ms\n"
+    );
+    assert_eq!(
+        Diff {
+            day: 7,
+            previous: Some(&first),
+            current: &second,
+            expected: Expected::parse("95437\n24933642\n"),
+        }
+        .to_string(),
+        "Day 7:
+  part 1: 95437 -> 95438 FAIL, expected 95437
+  part 2: 24933642 (unchanged) pass
+  time: 2.0ms -> 1.0ms (-50.0%)
+"
+    );
+}